@@ -0,0 +1,64 @@
+use commandor::prelude::*;
+
+use crate::lib::temp;
+use crate::lib::output::*;
+use crate::lib::format_size;
+
+pub struct CacheClean {
+    args: Vec<Box<dyn Argument>>
+}
+
+impl CacheClean {
+    pub fn new() -> Box<Self> {
+        Box::new(Self {
+            args: vec![
+                Default::new("--temp-dir", vec![], true)
+            ]
+        })
+    }
+}
+
+impl Command for CacheClean {
+    fn get_name(&self) -> &str {
+        "clean"
+    }
+
+    fn get_args(&self) -> &Vec<Box<dyn Argument>> {
+        &self.args
+    }
+
+    fn execute(&self, _: Vec<String>, values: Vec<ArgumentValue>) -> bool {
+        let mut temp_dir = None;
+
+        for arg in values {
+            match arg.name.as_str() {
+                "--temp-dir" => temp_dir = Some(arg.value),
+                _ => unreachable!()
+            }
+        }
+
+        let folder = temp::temp_folder(temp_dir.as_deref());
+
+        notice(format!("Cleaning staging data in {:?}...", folder.join(temp::STAGING_FOLDER)));
+
+        match temp::clean_stale(&folder) {
+            Ok(removed) if removed.is_empty() => notice("Nothing to remove"),
+            Ok(removed) => {
+                let total_size = removed.iter().map(|(_, size)| size).sum::<u64>();
+
+                notice({
+                    let mut output = vec![format!("Removed {} GB", format_size(total_size))];
+
+                    for (path, size) in &removed {
+                        output.push(format!("- {:?} ({} GB)", path, format_size(*size)));
+                    }
+
+                    output
+                });
+            },
+            Err(err) => error(format!("Failed to clean staging data: {}", err))
+        }
+
+        true
+    }
+}
@@ -0,0 +1,41 @@
+use commandor::prelude::*;
+
+pub mod clean;
+
+pub struct Cache {
+    args: Vec<Box<dyn Argument>>
+}
+
+impl Cache {
+    pub fn new() -> Box<Self> {
+        Box::new(Self {
+            args: vec![]
+        })
+    }
+}
+
+impl Command for Cache {
+    fn get_name(&self) -> &str {
+        "cache"
+    }
+
+    fn get_args(&self) -> &Vec<Box<dyn Argument>> {
+        &self.args
+    }
+
+    fn execute(&self, args: Vec<String>, _: Vec<ArgumentValue>) -> bool {
+        let manager = Manager::new(vec![
+            clean::CacheClean::new()
+        ]);
+    
+        match manager.execute(args[1..].to_vec()) {
+            Ok(_) => (),
+            
+            Err(Error::TooFewArguments) => eprintln!("Arguments required"),
+            Err(Error::CommandNotFound(command)) => eprintln!("Command {} not found", command),
+            Err(Error::ArgumentRequired(argument)) => eprintln!("Argument {} required", argument)
+        }
+
+        true
+    }
+}
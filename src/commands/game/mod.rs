@@ -2,6 +2,9 @@ use commandor::prelude::*;
 
 pub mod info;
 pub mod repair;
+pub mod update;
+pub mod rollback;
+pub mod relocate;
 
 pub struct Game {
     args: Vec<Box<dyn Argument>>
@@ -27,7 +30,10 @@ impl Command for Game {
     fn execute(&self, args: Vec<String>, _: Vec<ArgumentValue>) -> bool {
         let manager = Manager::new(vec![
             info::GameInfo::new(),
-            repair::GameRepair::new()
+            repair::GameRepair::new(),
+            update::GameUpdate::new(),
+            rollback::GameRollback::new(),
+            relocate::GameMove::new()
         ]);
     
         match manager.execute(args[1..].to_vec()) {
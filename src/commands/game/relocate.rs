@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf, Component};
+use std::fs::File;
+use std::os::unix::fs::MetadataExt;
+
+use commandor::prelude::*;
+
+use crate::lib::config;
+use crate::lib::output::*;
+use crate::lib::space;
+use crate::lib::lock::{Lock, LOCK_FILE};
+use crate::lib::{list_files, format_size, positional_args};
+
+pub struct GameMove {
+    args: Vec<Box<dyn Argument>>
+}
+
+impl GameMove {
+    pub fn new() -> Box<Self> {
+        Box::new(Self {
+            args: vec![
+                Flag::with_name("--ignore-space-check"),
+                Flag::with_name("--wait")
+            ]
+        })
+    }
+
+    /// Get absolute path without `..` segments and symlinks
+    /// 
+    /// The path may not exist yet, so its closest existing parent is canonicalized
+    /// and the rest of the path is appended to it
+    fn resolve<T: AsRef<Path>>(path: T) -> std::io::Result<PathBuf> {
+        let path = std::env::current_dir()?.join(path);
+
+        let mut existing = path.as_path();
+        let mut rest = Vec::new();
+
+        while !existing.exists() {
+            match (existing.parent(), existing.components().next_back()) {
+                (Some(parent), Some(component)) => {
+                    rest.push(component);
+
+                    existing = parent;
+                },
+                _ => break
+            }
+        }
+
+        let mut resolved = existing.canonicalize()?;
+
+        // Missing folders can't be symlinks so the rest is resolved lexically
+        for component in rest.into_iter().rev() {
+            match component {
+                Component::ParentDir => {
+                    resolved.pop();
+                },
+                Component::CurDir => (),
+                component => resolved.push(component)
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    /// Copy game files to another folder showing progress, then compare their sizes
+    ///
+    /// Hard-linked files (e.g. update backup) are linked in the new folder as well.
+    /// Modification times are kept because patch reverting relies on them
+    fn copy(from: &Path, to: &Path, files: &[PathBuf], total_size: u64) -> anyhow::Result<()> {
+        let mut progress = linya::Progress::new();
+        let bar = progress.bar(total_size as usize, format!("Copying ({} GB)", format_size(total_size)));
+
+        // Empty folders are not listed with the files
+        let mut folders = vec![PathBuf::new()];
+
+        while let Some(folder) = folders.pop() {
+            std::fs::create_dir_all(to.join(&folder))?;
+
+            for entry in std::fs::read_dir(from.join(&folder))? {
+                let entry = entry?;
+
+                if entry.file_type()?.is_dir() {
+                    folders.push(folder.join(entry.file_name()));
+                }
+            }
+        }
+
+        // Inode of the original file and path to its copy
+        let mut copied = HashMap::new();
+
+        for file in files {
+            let source = from.join(file);
+            let target = to.join(file);
+
+            let metadata = std::fs::symlink_metadata(&source)?;
+
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            if metadata.file_type().is_symlink() {
+                std::os::unix::fs::symlink(std::fs::read_link(&source)?, &target)?;
+            }
+
+            else if let Some(copy) = copied.get(&(metadata.dev(), metadata.ino())) {
+                std::fs::hard_link(copy, &target)?;
+            }
+
+            else {
+                std::fs::copy(&source, &target)?;
+
+                File::open(&target)?.set_modified(metadata.modified()?)?;
+
+                if metadata.nlink() > 1 {
+                    copied.insert((metadata.dev(), metadata.ino()), target.clone());
+                }
+
+                progress.inc_and_draw(&bar, metadata.len() as usize);
+            }
+        }
+
+        println!();
+
+        for file in files {
+            let original = std::fs::symlink_metadata(from.join(file))?.len();
+
+            match std::fs::symlink_metadata(to.join(file)) {
+                Ok(metadata) if metadata.len() == original => (),
+                Ok(metadata) => anyhow::bail!("Size of the copied {:?} is {} bytes instead of {}", file, metadata.len(), original),
+                Err(err) => anyhow::bail!("Failed to check copied {:?}: {}", file, err)
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Command for GameMove {
+    fn get_name(&self) -> &str {
+        "move"
+    }
+
+    fn get_args(&self) -> &Vec<Box<dyn Argument>> {
+        &self.args
+    }
+
+    fn execute(&self, args: Vec<String>, values: Vec<ArgumentValue>) -> bool {
+        let mut config = config::get().expect("Failed to load config");
+
+        let mut ignore_space_check = false;
+        let mut wait = false;
+
+        for arg in &values {
+            match arg.name.as_str() {
+                "--ignore-space-check" => ignore_space_check = true,
+                "--wait" => wait = true,
+                _ => unreachable!()
+            }
+        }
+
+        if config.paths.game.is_empty() {
+            error("You didn't specify the game path\n");
+
+            // Interrupt command execution
+            return false;
+        }
+
+        let new_path = match positional_args(&args[1..], &[]).first() {
+            Some(path) => match Self::resolve(path) {
+                Ok(path) => path,
+                Err(err) => {
+                    error(format!("Failed to resolve the new game path: {}", err));
+
+                    return false;
+                }
+            },
+            None => {
+                error("You didn't specify the new game path");
+
+                return false;
+            }
+        };
+
+        let game_path = match Path::new(&config.paths.game).canonicalize() {
+            Ok(path) => path,
+            Err(err) => {
+                error(format!("Failed to find the game folder: {}", err));
+
+                return false;
+            }
+        };
+
+        if new_path.starts_with(&game_path) {
+            error("The game can't be moved inside of its own folder");
+
+            return false;
+        }
+
+        if new_path.exists() && new_path.read_dir().map(|mut entries| entries.next().is_some()).unwrap_or(true) {
+            error(format!("Folder {:?} is not empty", new_path));
+
+            return false;
+        }
+
+        let lock = match Lock::acquire(&game_path, wait) {
+            Ok(lock) => lock,
+            Err(err) => {
+                error(err.to_string());
+
+                return false;
+            }
+        };
+
+        notice(format!("Moving the game: {:?} -> {:?}", game_path, new_path));
+
+        // Moving the folder within one filesystem is just renaming
+        let renamed = {
+            if let Some(parent) = new_path.parent() {
+                std::fs::create_dir_all(parent).ok();
+            }
+
+            // Rename can replace only empty folders
+            std::fs::remove_dir(&new_path).ok();
+
+            std::fs::rename(&game_path, &new_path).is_ok()
+        };
+
+        // The lock file is moved together with the game and stays locked
+        if !renamed {
+            let files = match list_files(&game_path) {
+                Ok(files) => files.into_iter()
+                    .filter(|file| file != Path::new(LOCK_FILE))
+                    .collect::<Vec<PathBuf>>(),
+                Err(err) => {
+                    error(format!("Failed to list game files: {}", err));
+
+                    return false;
+                }
+            };
+
+            // Hard-linked data is copied only once
+            let mut inodes = HashMap::new();
+
+            for file in &files {
+                if let Ok(metadata) = std::fs::symlink_metadata(game_path.join(file)) {
+                    inodes.insert((metadata.dev(), metadata.ino()), metadata.len());
+                }
+            }
+
+            let total_size = inodes.values().sum::<u64>();
+
+            if !space::preflight(&[(new_path.clone(), total_size)], ignore_space_check) {
+                return false;
+            }
+
+            if let Err(err) = Self::copy(&game_path, &new_path, &files, total_size) {
+                error(format!("Failed to copy the game: {}. Original game folder was kept", err));
+
+                std::fs::remove_dir_all(&new_path).ok();
+
+                return false;
+            }
+        }
+
+        config.paths.game = new_path.to_string_lossy().to_string();
+
+        if let Err(err) = config::update(config) {
+            error(format!("Failed to update config: {}", err));
+
+            // Return the game back so it's still located where the config says
+            if renamed {
+                std::fs::rename(&new_path, &game_path).ok();
+            } else {
+                std::fs::remove_dir_all(&new_path).ok();
+            }
+
+            return false;
+        }
+
+        if !renamed {
+            drop(lock);
+
+            if let Err(err) = std::fs::remove_dir_all(&game_path) {
+                warn(format!("Failed to remove the old game folder: {}", err));
+            }
+        }
+
+        notice(format!("Game was moved to {:?}", new_path));
+
+        true
+    }
+}
@@ -1,8 +1,16 @@
 use commandor::prelude::*;
 
-use anime_game_core::genshin::repairer::try_get_integrity_files;
 use anime_game_core::repairer::IntegrityFile;
 
+use crate::lib::config;
+use crate::lib::orphans;
+use crate::lib::lock::Lock;
+use crate::lib::integrity;
+use crate::lib::positional_args;
+use crate::lib::manifest::ManifestFile;
+use crate::lib::health::VerificationScope;
+use crate::lib::output::*;
+use crate::lib::format_size;
 use crate::lib::command_traits::repair::*;
 
 pub struct GameRepair {
@@ -10,17 +18,143 @@ pub struct GameRepair {
 }
 
 impl RepairFiles for GameRepair {
-    fn try_get_integrity_files(_: Vec<String>) -> anyhow::Result<Vec<IntegrityFile>> {
-        try_get_integrity_files(None)
+    fn try_get_integrity_files(_: Vec<String>, repairing_config: &RepairFilesConfig, manifest: Option<&[ManifestFile]>) -> anyhow::Result<(Vec<IntegrityFile>, VerificationScope)> {
+        let mut files = integrity::game_files(manifest)?;
+
+        let mut scope = VerificationScope {
+            game: true,
+            ..VerificationScope::default()
+        };
+
+        if repairing_config.all {
+            let config = config::get().expect("Failed to get config");
+
+            for locale in integrity::installed_locales(&config.paths.game)? {
+                files.append(&mut integrity::voice_files(locale, manifest)?);
+
+                scope.voices.push(locale.to_name().to_string());
+            }
+        }
+
+        Ok((files, scope))
     }
 }
 
 impl GameRepair {
     pub fn new() -> Box<Self> {
+        let mut args = Self::get_command_args();
+
+        args.push(Flag::with_name("--orphans")); // List files which are not in the integrity files
+        args.push(Flag::with_name("--clean-orphans")); // Same as --orphans, but also delete them
+
         Box::new(Self {
-            args: Self::get_command_args()
+            args
         })
     }
+
+    /// Find files inside the game folder which are not listed in game or installed voice packages integrity files
+    fn orphans(clean: bool, wait: bool) -> bool {
+        let config = config::get().expect("Failed to load config");
+
+        if config.paths.game.is_empty() {
+            error("You didn't specify the game path\n");
+
+            // Interrupt command execution
+            return false;
+        }
+
+        let _lock = if clean {
+            match Lock::acquire(&config.paths.game, wait) {
+                Ok(lock) => Some(lock),
+                Err(err) => {
+                    error(err.to_string());
+
+                    return false;
+                }
+            }
+        } else {
+            None
+        };
+
+        notice("Fetching integrity files...");
+
+        let mut files = match integrity::game_files(None) {
+            Ok(files) => files,
+            Err(err) => {
+                error(format!("Failed to get integrity files: {}", err));
+
+                return false;
+            }
+        };
+
+        // Voice packages files are not listed in the game integrity files
+        match integrity::installed_locales(&config.paths.game) {
+            Ok(locales) => {
+                for locale in locales {
+                    match integrity::voice_files(locale, None) {
+                        Ok(mut voice_files) => files.append(&mut voice_files),
+                        Err(err) => {
+                            error(format!("Failed to get {} package integrity files: {}", locale.to_name(), err));
+
+                            return false;
+                        }
+                    }
+                }
+            },
+            Err(err) => {
+                error(format!("Failed to get installed voice packages: {}", err));
+
+                return false;
+            }
+        }
+
+        notice("Searching for unknown files...");
+
+        let orphans = match orphans::find(&config.paths.game, &files) {
+            Ok(orphans) => orphans,
+            Err(err) => {
+                error(format!("Failed to list game files: {}", err));
+
+                return false;
+            }
+        };
+
+        if orphans.is_empty() {
+            notice("No unknown files found");
+
+            return true;
+        }
+
+        let total_size = orphans.iter().map(|(_, size)| size).sum::<u64>();
+
+        notice({
+            let mut output = vec![format!("Found {} unknown files ({} GB)", orphans.len(), format_size(total_size))];
+
+            for (path, _) in &orphans {
+                output.push(format!("- {:?}", path));
+            }
+
+            output
+        });
+
+        if clean {
+            println!();
+            notice("Removing unknown files...");
+
+            let mut removed = 0;
+
+            for (path, _) in &orphans {
+                match std::fs::remove_file(std::path::Path::new(&config.paths.game).join(path)) {
+                    Ok(_) => removed += 1,
+                    Err(err) => error(format!("Failed to remove {:?}: {}", path, err))
+                }
+            }
+
+            notice(format!("Removed {} of {} files", removed, orphans.len()));
+        }
+
+        true
+    }
 }
 
 impl Command for GameRepair {
@@ -33,6 +167,13 @@ impl Command for GameRepair {
     }
 
     fn execute(&self, args: Vec<String>, values: Vec<ArgumentValue>) -> bool {
-        Self::repair(RepairFilesConfig::from_args(values), args)
+        let args = positional_args(&args[1..], &Self::get_value_args());
+        let config = RepairFilesConfig::from_args(values);
+
+        if config.orphans || config.clean_orphans {
+            Self::orphans(config.clean_orphans, config.wait)
+        } else {
+            Self::repair(config, args)
+        }
     }
 }
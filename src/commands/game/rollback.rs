@@ -0,0 +1,93 @@
+use commandor::prelude::*;
+use colorful::Colorful;
+
+use crate::lib::config;
+use crate::lib::output::*;
+use crate::lib::snapshot;
+use crate::lib::lock::Lock;
+
+pub struct GameRollback {
+    args: Vec<Box<dyn Argument>>
+}
+
+impl GameRollback {
+    pub fn new() -> Box<Self> {
+        Box::new(Self {
+            args: vec![
+                Flag::with_name("--wait")
+            ]
+        })
+    }
+}
+
+impl Command for GameRollback {
+    fn get_name(&self) -> &str {
+        "rollback"
+    }
+
+    fn get_args(&self) -> &Vec<Box<dyn Argument>> {
+        &self.args
+    }
+
+    fn execute(&self, _: Vec<String>, values: Vec<ArgumentValue>) -> bool {
+        let config = config::get().expect("Failed to load config");
+
+        let mut wait = false;
+
+        for arg in &values {
+            match arg.name.as_str() {
+                "--wait" => wait = true,
+                _ => unreachable!()
+            }
+        }
+
+        if config.paths.game.is_empty() {
+            error("You didn't specify the game path\n");
+
+            // Interrupt command execution
+            return false;
+        }
+
+        let game_path = config.paths.game;
+
+        let _lock = match Lock::acquire(&game_path, wait) {
+            Ok(lock) => lock,
+            Err(err) => {
+                error(err.to_string());
+
+                return false;
+            }
+        };
+
+        match snapshot::Manifest::read(&game_path) {
+            Ok(Some(backup)) => notice(format!(
+                "Rolling back the game: {} -> {}...",
+                backup.updated_to.light_yellow(),
+                backup.version.light_green()
+            )),
+            Ok(None) => {
+                error("There's no backup of the previous game version");
+
+                return false;
+            },
+            Err(err) => {
+                error(format!("Failed to read update backup: {}", err));
+
+                return false;
+            }
+        }
+
+        match snapshot::restore(&game_path) {
+            Ok(backup) => {
+                notice(format!("Game was rolled back to {}", backup.version.light_green()));
+
+                true
+            },
+            Err(err) => {
+                error(format!("Failed to roll back the game: {}", err));
+
+                false
+            }
+        }
+    }
+}
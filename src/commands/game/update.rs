@@ -0,0 +1,345 @@
+use std::path::{Path, PathBuf, Component};
+use std::sync::{Arc, Mutex};
+
+use commandor::prelude::*;
+use colorful::Colorful;
+use serde::Deserialize;
+
+use anime_game_core::prelude::*;
+use anime_game_core::genshin::prelude::*;
+use anime_game_core::installer::downloader::Downloader;
+use anime_game_core::installer::archives::Archive;
+
+use crate::lib::config;
+use crate::lib::output::*;
+use crate::lib::space;
+use crate::lib::temp;
+use crate::lib::snapshot;
+use crate::lib::patch;
+use crate::lib::lock::Lock;
+use crate::lib::format_size;
+
+/// List of files patched by `.hdiff` files from the archive
+const HDIFF_FILES: &str = "hdifffiles.txt";
+
+/// List of files removed by the update
+const DELETE_FILES: &str = "deletefiles.txt";
+
+#[derive(Deserialize)]
+struct HdiffFile {
+    #[serde(rename = "remoteName")]
+    remote_name: String
+}
+
+pub struct GameUpdate {
+    args: Vec<Box<dyn Argument>>
+}
+
+impl GameUpdate {
+    pub fn new() -> Box<Self> {
+        Box::new(Self {
+            args: vec![
+                Flag::with_name("--ignore-space-check"),
+                Default::new("--temp-dir", vec![], true),
+                Flag::with_name("--wait")
+            ]
+        })
+    }
+
+    /// Download, back up the game and install the update, rolling back on failure
+    /// 
+    /// The archive is installed from the same download it was inspected in,
+    /// so the backup has exactly the files it replaces
+    fn update(diff: VersionDiff, game_path: String, staging_folder: PathBuf, ignore_space_check: bool) -> anyhow::Result<()> {
+        let (current, latest, url, download_size, unpacked_size) = match &diff {
+            VersionDiff::Diff { current, latest, url, download_size, unpacked_size } => (*current, *latest, url.clone(), *download_size, *unpacked_size),
+            _ => unreachable!()
+        };
+
+        let archive_path = staging_folder.join(url.split('/').next_back().unwrap_or("update.zip"));
+
+        let progress = Arc::new(Mutex::new(linya::Progress::new()));
+
+        let downloading_bar = progress.lock().unwrap().bar(
+            download_size as usize,
+            format!("Downloading ({} GB)", format_size(download_size))
+        );
+
+        Downloader::new(&url)?.download_to(archive_path.to_string_lossy(), move |curr, _| {
+            progress.lock().unwrap().set_and_draw(&downloading_bar, curr as usize);
+        })?;
+
+        println!();
+
+        let mut archive = match Archive::open(archive_path.to_string_lossy()) {
+            Some(archive) => archive,
+            None => anyhow::bail!("Failed to open downloaded archive")
+        };
+
+        let entries = archive.get_entries().into_iter().map(|entry| entry.name).collect::<Vec<String>>();
+
+        let affected = snapshot::affected_files(&game_path, &entries);
+
+        // Copies of the replaced files are stored in the game folder together with the new files
+        let backup_size = snapshot::size(&game_path, &affected);
+
+        if !space::preflight(&[(PathBuf::from(&game_path), unpacked_size + backup_size)], ignore_space_check) {
+            anyhow::bail!("Not enough free space to back up {} GB of replaced files and install the update", format_size(backup_size));
+        }
+
+        notice(format!("Backing up {} files ({} GB) replaced by the update...", affected.len(), format_size(backup_size)));
+
+        let mut backup = snapshot::create(&game_path, current.to_string(), latest.to_string(), &affected)?;
+
+        notice(format!("Unpacking ({} GB)...", format_size(unpacked_size)));
+
+        if let Err(err) = Self::install(&mut archive, Path::new(&game_path), &mut backup, latest) {
+            warn(format!("Failed to update the game: {}. Rolling back to {}...", err, current));
+
+            if let Err(rollback_err) = snapshot::restore(&game_path) {
+                anyhow::bail!("Failed to roll back the game: {}. Use `game rollback` to try again", rollback_err);
+            }
+
+            anyhow::bail!("Game was rolled back to {}", current);
+        }
+
+        // The update is considered finished only now
+        backup.completed = true;
+        backup.write(&game_path)?;
+
+        snapshot::stash_patch_backup(&game_path)?;
+
+        Ok(())
+    }
+
+    /// Unpack the archive to the game folder, apply hdiff patches and remove deleted files
+    /// 
+    /// Version file is written only when everything else succeeded
+    fn install(archive: &mut Archive, game_path: &Path, backup: &mut snapshot::Manifest, latest: Version) -> anyhow::Result<()> {
+        archive.extract(game_path.to_string_lossy())?;
+
+        let hdiff_files = game_path.join(HDIFF_FILES);
+
+        if hdiff_files.exists() {
+            for file in Self::read_list(&hdiff_files, |line| serde_json::from_str::<HdiffFile>(line).map(|file| file.remote_name).ok())? {
+                let target = game_path.join(&file);
+                let patch = game_path.join(format!("{}.hdiff", file.to_string_lossy()));
+                let patched = game_path.join(format!("{}.hdiff_patched", file.to_string_lossy()));
+
+                let output = std::process::Command::new("hpatchz")
+                    .arg("-f")
+                    .arg(&target)
+                    .arg(&patch)
+                    .arg(&patched)
+                    .output()?;
+
+                if !output.status.success() {
+                    anyhow::bail!("Failed to patch {:?}: {}", file, String::from_utf8_lossy(&output.stderr).trim());
+                }
+
+                std::fs::rename(&patched, &target)?;
+                std::fs::remove_file(&patch)?;
+            }
+
+            std::fs::remove_file(&hdiff_files)?;
+        }
+
+        let delete_files = game_path.join(DELETE_FILES);
+
+        if delete_files.exists() {
+            let files = Self::read_list(&delete_files, |line| Some(line.to_string()))?;
+
+            // Removed files are kept in the backup so `game rollback` can restore them
+            snapshot::move_to_backup(game_path, backup, &files)?;
+
+            std::fs::remove_file(&delete_files)?;
+        }
+
+        std::fs::write(game_path.join(snapshot::VERSION_FILE), latest.version)?;
+
+        Ok(())
+    }
+
+    /// Read paths from the list file unpacked from the archive
+    /// 
+    /// Paths leaving the game folder are rejected
+    fn read_list(path: &Path, parse: impl Fn(&str) -> Option<String>) -> anyhow::Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+
+        for line in std::fs::read_to_string(path)?.lines() {
+            let line = line.trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let file = match parse(line) {
+                Some(file) => PathBuf::from(file),
+                None => anyhow::bail!("Wrong line in {:?}: {}", path, line)
+            };
+
+            if !file.components().all(|component| matches!(component, Component::Normal(_))) {
+                anyhow::bail!("Wrong path in {:?}: {:?}", path, file);
+            }
+
+            files.push(file);
+        }
+
+        Ok(files)
+    }
+}
+
+impl Command for GameUpdate {
+    fn get_name(&self) -> &str {
+        "update"
+    }
+
+    fn get_args(&self) -> &Vec<Box<dyn Argument>> {
+        &self.args
+    }
+
+    fn execute(&self, _: Vec<String>, values: Vec<ArgumentValue>) -> bool {
+        let config = config::get().expect("Failed to load config");
+
+        let mut ignore_space_check = false;
+        let mut temp_dir = None;
+        let mut wait = false;
+
+        for arg in &values {
+            match arg.name.as_str() {
+                "--ignore-space-check" => ignore_space_check = true,
+                "--temp-dir" => temp_dir = Some(arg.value.clone()),
+                "--wait" => wait = true,
+                _ => unreachable!()
+            }
+        }
+
+        if config.paths.game.is_empty() {
+            error("You didn't specify the game path\n");
+
+            // Interrupt command execution
+            return false;
+        }
+
+        let game_path = config.paths.game.clone();
+
+        let _lock = match Lock::acquire(&game_path, wait) {
+            Ok(lock) => lock,
+            Err(err) => {
+                error(err.to_string());
+
+                return false;
+            }
+        };
+
+        match snapshot::Manifest::read(&game_path) {
+            Ok(Some(backup)) if !backup.completed => {
+                error(vec![
+                    format!("Previous update to {} was interrupted", backup.updated_to),
+                    format!("Use `game rollback` to restore {} version before updating", backup.version)
+                ]);
+
+                return false;
+            },
+            Err(err) => warn(format!("Failed to read update backup: {}", err)),
+            _ => ()
+        }
+
+        let diff = match Game::new(&game_path).try_get_diff() {
+            Ok(diff) => diff,
+            Err(err) => {
+                error(format!("Failed to find game version difference: {}", err));
+
+                return false;
+            }
+        };
+
+        let (current, latest, download_size, unpacked_size) = match &diff {
+            VersionDiff::Diff { current, latest, download_size, unpacked_size, .. } => (*current, *latest, *download_size, *unpacked_size),
+
+            VersionDiff::Latest(version) => {
+                notice(format!("Game is already updated to the latest version: {}", version.to_string().light_green()));
+
+                return true;
+            },
+            VersionDiff::Predownload { current, latest, .. } => {
+                notice(format!(
+                    "Game is updated to the latest version: {}. {} version is available only for pre-downloading",
+                    current.to_string().light_green(),
+                    latest.to_string().light_yellow()
+                ));
+
+                return true;
+            },
+            VersionDiff::Outdated { current, latest } => {
+                error(vec![
+                    "Your game installation is too outdated to be updated".to_string(),
+                    format!("Current version: {}", current.to_string().light_red()),
+                    format!("Latest version: {}", latest.to_string().light_green())
+                ]);
+
+                return false;
+            },
+            VersionDiff::NotInstalled { .. } => {
+                error("Game is not installed");
+
+                return false;
+            }
+        };
+
+        let staging_folder = match temp::staging_folder(temp_dir.as_deref()) {
+            Ok(folder) => folder,
+            Err(err) => {
+                error(format!("Failed to create staging folder: {}", err));
+
+                return false;
+            }
+        };
+
+        // Space for the replaced files backup is checked once the archive is inspected
+        let requirements = vec![
+            (staging_folder.clone(), download_size),
+            (PathBuf::from(&game_path), unpacked_size)
+        ];
+
+        if !space::preflight(&requirements, ignore_space_check) {
+            return false;
+        }
+
+        notice(format!(
+            "Updating the game: {} -> {}",
+            current.to_string().light_yellow(),
+            latest.to_string().light_green()
+        ));
+
+        let result = Self::update(diff, game_path.clone(), staging_folder, ignore_space_check);
+
+        temp::remove_staging_folder(temp_dir.as_deref());
+
+        match result {
+            Ok(()) => {
+                notice(vec![
+                    format!("Game updated to {}", latest.to_string().light_green()),
+                    "Previous version can be restored with `game rollback` until the next update".to_string()
+                ]);
+
+                // The patch should be applied again to the new game version
+                if config.patch.auto_apply {
+                    let result = Patch::try_fetch(config.patch.hosts.clone(), None)
+                        .and_then(|patch_info| patch::auto_apply(&config, &patch_info));
+
+                    if let Err(err) = result {
+                        warn(format!("Failed to apply the patch automatically: {}", err));
+                    }
+                }
+
+                true
+            },
+            Err(err) => {
+                error(err.to_string());
+
+                false
+            }
+        }
+    }
+}
@@ -30,7 +30,9 @@ impl Command for Help {
             "game:",
             "├─ info: Get installed game info",
             "├─ download: Download the game (WIP)",
-            "├─ update: Update the game (WIP)",
+            "├─ update: Update the game",
+            "├─ rollback: Restore the game version before the last update",
+            "├─ move: Move the game to another folder",
             "└─ repair: Repair the game",
             "",
             "voice:",
@@ -44,7 +46,25 @@ impl Command for Help {
             "├─ info: Get info about the GNU/Linux patch",
             "├─ sync: Sync latest patch from remote repo",
             "├─ apply: Apply patch",
-            "└─ revert: Revert patch",
+            "├─ revert: Revert patch",
+            "├─ hosts:",
+            "│  ├─ check: Check if telemetry servers are blocked",
+            "│  ├─ add: Block telemetry servers in /etc/hosts",
+            "│  ├─ remove: Unblock telemetry servers in /etc/hosts",
+            "│  └─ print: Print /etc/hosts lines blocking telemetry servers",
+            "├─ log: List recent patch revisions",
+            "└─ mirrors:",
+            "   └─ check: Check patch hosts health and latest revisions",
+            "",
+            "repair:",
+            "├─ restore: Restore quarantined files",
+            "├─ manifest:",
+            "│  ├─ export: Save game and voice packages integrity files",
+            "│  └─ generate: Create integrity files from the installed game",
+            "└─ schedule: Verify files regularly using systemd timers",
+            "",
+            "cache:",
+            "└─ clean: Remove data left by interrupted downloads",
             "",
             "info: Get info about the game, patch and voice packages",
             "run: Run the game",
@@ -7,6 +7,7 @@ use anime_game_core::genshin::prelude::*;
 use crate::lib::config;
 use crate::lib::output::*;
 use crate::lib::format_size;
+use crate::lib::health::VerificationState;
 
 pub struct Info {
     args: Vec<Box<dyn Argument>>
@@ -117,6 +118,28 @@ impl Command for Info {
             Err(err) => error(format!("Failed to fetch patch status: {err}"))
         }
 
+        // Files verification
+
+        match VerificationState::read() {
+            Ok(Some(state)) => {
+                let message = format!("Last verified: {}{}, {} broken files", state.verified_at(), if state.fast { " (fast)" } else { "" }, state.broken.len());
+
+                // States saved before the scope was remembered don't have it
+                let message = match state.scope.describe() {
+                    scope if scope.is_empty() => message,
+                    scope => format!("{message} ({scope})")
+                };
+
+                if state.broken.is_empty() {
+                    notice(message);
+                } else {
+                    warn(message);
+                }
+            },
+            Ok(None) => notice("Files were never verified"),
+            Err(err) => error(format!("Failed to read verification state: {err}"))
+        }
+
         // Installed voice packages
 
         println!("\n Installed voice packages:");
@@ -2,6 +2,8 @@ mod info;
 mod voice;
 mod patch;
 mod game;
+mod repair;
+mod cache;
 mod run;
 mod help;
 
@@ -9,5 +11,7 @@ pub use info::Info;
 pub use voice::Voice;
 pub use patch::Patch;
 pub use game::Game;
+pub use repair::Repair;
+pub use cache::Cache;
 pub use run::Run;
 pub use help::Help;
@@ -2,9 +2,14 @@ use commandor::prelude::*;
 use colorful::Colorful;
 
 use anime_game_core::prelude::genshin::*;
+use anime_game_core::version::ToVersion;
 
 use crate::lib::config;
 use crate::lib::output::*;
+use crate::lib::lock::Lock;
+use crate::lib::hash;
+use crate::lib::patch::{self, PatchScript};
+use crate::lib::signature;
 
 pub struct PatchApply {
     args: Vec<Box<dyn Argument>>
@@ -14,10 +19,100 @@ impl PatchApply {
     pub fn new() -> Box<Self> {
         Box::new(Self {
             args: vec![
-                Flag::with_name("--no-root")
+                Flag::with_name("--no-root"),
+                Flag::with_name("--dry-run"),
+                Flag::with_name("--wait")
             ]
         })
     }
+
+    /// Print files and hosts file lines the patch would change without applying it
+    fn dry_run(config: &config::Config, patch_info: &Patch, no_root: bool) {
+        let version = match patch_info.to_version() {
+            Some(version) => version,
+            None => {
+                error("Patch is not available");
+
+                return;
+            }
+        };
+
+        if let Err(err) = patch::check_revision(config, patch_info) {
+            warn(format!("Patch won't be applied: {}", err));
+        }
+
+        let script = match PatchScript::read(&config.paths.patch, version) {
+            Ok(script) => script,
+            Err(err) => {
+                error(format!("Failed to read patch script for {} version: {}", version, err));
+
+                return;
+            }
+        };
+
+        let mut output = vec![format!("Files to be modified by {:?}:", script.path)];
+
+        for file in &script.files {
+            let status = patch::file_status(&config.paths.game, file, &script);
+
+            output.push(format!("- {} ({})", file, status.to_name()));
+
+            if status != patch::FileStatus::Missing {
+                output.push(format!("  current hash: {}", match hash::md5(std::path::Path::new(&config.paths.game).join(file)) {
+                    Ok(hash) => hash,
+                    Err(err) => format!("failed to calculate: {}", err)
+                }));
+            }
+
+            output.push(match script.expected_hashes(file).as_slice() {
+                [] => String::from("  expected hash: unknown, the script doesn't check it"),
+                [hash] => format!("  expected hash: {}", hash),
+                hashes => format!("  expected hashes: {}", hashes.join(", "))
+            });
+        }
+
+        notice(output);
+
+        match signature::verify(&config.paths.patch, &config.patch.trusted_keys) {
+            Ok(signature) => notice(format!("Patch signature: {}", signature)),
+            Err(err) if config.patch.require_signature => error(format!("{}. Patch won't be applied as patch.require_signature is enabled", err)),
+            Err(err) => warn(format!("Patch signature: {}", err))
+        }
+
+        let missing = match patch::blocked_servers(&script.servers) {
+            Ok(servers) => servers.into_iter()
+                .filter(|(_, blocked)| !blocked)
+                .map(|(server, _)| server)
+                .collect::<Vec<String>>(),
+            Err(err) => {
+                warn(format!("Failed to read {}: {}", patch::HOSTS_FILE, err));
+
+                script.servers.clone()
+            }
+        };
+
+        if missing.is_empty() {
+            notice(format!("All telemetry servers are already blocked in {}", patch::HOSTS_FILE));
+        }
+
+        else {
+            let mut output = vec![format!("Lines to be added to {}:", patch::HOSTS_FILE)];
+
+            for server in &missing {
+                output.push(format!("  0.0.0.0 {}", server));
+            }
+
+            notice(output);
+        }
+
+        if no_root {
+            notice(format!("Patch script will be run without root, so {} won't be modified", patch::HOSTS_FILE));
+        } else if missing.is_empty() {
+            notice("Root is not needed, but patch script will be run with pkexec anyway. Use --no-root to avoid it");
+        } else {
+            notice(format!("Root is needed to modify {}. Patch script will be run with pkexec", patch::HOSTS_FILE));
+        }
+    }
 }
 
 impl Command for PatchApply {
@@ -39,32 +134,61 @@ impl Command for PatchApply {
         }
 
         let mut no_root = false;
+        let mut dry_run = false;
+        let mut wait = false;
 
         for arg in args {
-            match arg.value.as_str() {
+            match arg.name.as_str() {
                 "--no-root" => no_root = true,
+                "--dry-run" => dry_run = true,
+                "--wait" => wait = true,
                 _ => unreachable!()
             }
         }
 
-        let patch = PatchApplier::new(config.paths.patch);
+        // Dry run doesn't modify anything
+        let _lock = if !dry_run {
+            match Lock::acquire(&config.paths.game, wait) {
+                Ok(lock) => Some(lock),
+                Err(err) => {
+                    error(err.to_string());
 
-        match patch.is_sync(&config.patch.hosts) {
+                    return false;
+                }
+            }
+        } else {
+            None
+        };
+
+        match patch::is_synced(&config) {
             // Local patch is synced
             Ok(true) => {
                 notice("Fetching latest patch info...");
 
-                match Patch::try_fetch(config.patch.hosts, None) {
+                match Patch::try_fetch(config.patch.hosts.clone(), None) {
                     // Successfully fetched latest patch info
+                    Ok(patch_info) if dry_run => Self::dry_run(&config, &patch_info, no_root),
+
                     Ok(patch_info) => {
                         match patch_info.is_applied(&config.paths.game) {
                             // Patch is not applied to the game
                             Ok(false) => {
                                 notice("Applying patch...");
 
-                                match patch.apply(config.paths.game, patch_info, !no_root) {
-                                    Ok(_) => notice("Patch successfully applied"),
-                                    Err(err) => error(format!("Failed to apply patch: {}", err))
+                                match patch::apply(&config, patch_info, !no_root) {
+                                    Ok(backup) if no_root => notice(vec![
+                                        "Patch successfully applied".to_string(),
+                                        format!("Original files are backed up to {:?}", backup),
+                                        format!("Telemetry servers were not blocked. Run {} or enable patch.isolated_hosts in the config", "patch hosts add".light_yellow())
+                                    ]),
+                                    Ok(backup) => notice(vec![
+                                        "Patch successfully applied".to_string(),
+                                        format!("Original files are backed up to {:?}", backup)
+                                    ]),
+                                    Err(err) => error(vec![
+                                        format!("Failed to apply patch: {}", err),
+                                        format!("Original files can be restored with {}", "patch revert".light_yellow())
+                                    ])
                                 }
                             },
                             Ok(true) => notice("Patch is already applied"),
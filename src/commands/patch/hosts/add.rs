@@ -0,0 +1,53 @@
+use commandor::prelude::*;
+
+use crate::lib::config;
+use crate::lib::output::*;
+use crate::lib::hosts;
+use crate::lib::patch::HOSTS_FILE;
+
+pub struct PatchHostsAdd {
+    args: Vec<Box<dyn Argument>>
+}
+
+impl PatchHostsAdd {
+    pub fn new() -> Box<Self> {
+        Box::new(Self {
+            args: vec![]
+        })
+    }
+}
+
+impl Command for PatchHostsAdd {
+    fn get_name(&self) -> &str {
+        "add"
+    }
+
+    fn get_args(&self) -> &Vec<Box<dyn Argument>> {
+        &self.args
+    }
+
+    fn execute(&self, _: Vec<String>, _: Vec<ArgumentValue>) -> bool {
+        let config = config::get().expect("Failed to load config");
+
+        let servers = match super::servers(&config) {
+            Some(servers) => servers,
+            None => return false
+        };
+
+        let content = match std::fs::read_to_string(HOSTS_FILE) {
+            Ok(content) => content,
+            Err(err) => {
+                error(format!("Failed to read {}: {}", HOSTS_FILE, err));
+
+                return false;
+            }
+        };
+
+        match hosts::write(HOSTS_FILE, &hosts::with_entries(&content, &servers)) {
+            Ok(()) => notice(format!("Blocked {} telemetry servers in {}", servers.len(), HOSTS_FILE)),
+            Err(err) => error(format!("Failed to update {}: {}", HOSTS_FILE, err))
+        }
+
+        true
+    }
+}
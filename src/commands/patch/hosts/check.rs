@@ -0,0 +1,71 @@
+use commandor::prelude::*;
+use colorful::Colorful;
+
+use crate::lib::config;
+use crate::lib::output::*;
+use crate::lib::hosts;
+use crate::lib::patch::{self, HOSTS_FILE};
+
+pub struct PatchHostsCheck {
+    args: Vec<Box<dyn Argument>>
+}
+
+impl PatchHostsCheck {
+    pub fn new() -> Box<Self> {
+        Box::new(Self {
+            args: vec![]
+        })
+    }
+}
+
+impl Command for PatchHostsCheck {
+    fn get_name(&self) -> &str {
+        "check"
+    }
+
+    fn get_args(&self) -> &Vec<Box<dyn Argument>> {
+        &self.args
+    }
+
+    fn execute(&self, _: Vec<String>, _: Vec<ArgumentValue>) -> bool {
+        let config = config::get().expect("Failed to load config");
+
+        let servers = match super::servers(&config) {
+            Some(servers) => servers,
+            None => return false
+        };
+
+        let managed = std::fs::read_to_string(HOSTS_FILE)
+            .map(|hosts| hosts::has_entries(&hosts))
+            .unwrap_or(false);
+
+        match patch::blocked_servers(&servers) {
+            Ok(servers) => {
+                let mut output = vec![format!("Telemetry servers in {}:", HOSTS_FILE)];
+
+                for (server, blocked) in &servers {
+                    output.push(format!("- {}: {}", server, if *blocked {
+                        "blocked".light_green()
+                    } else {
+                        "not blocked".light_red()
+                    }));
+                }
+
+                if managed {
+                    output.push(String::from("Entries are managed by `patch hosts`"));
+                }
+
+                if servers.iter().all(|(_, blocked)| *blocked) {
+                    notice(output);
+                } else {
+                    output.push(format!("Run {} to block them", "patch hosts add".light_yellow()));
+
+                    warn(output);
+                }
+            },
+            Err(err) => error(format!("Failed to read {}: {}", HOSTS_FILE, err))
+        }
+
+        true
+    }
+}
@@ -0,0 +1,68 @@
+use commandor::prelude::*;
+
+use crate::lib::config::Config;
+use crate::lib::output::*;
+use crate::lib::patch::PatchScript;
+
+pub mod check;
+pub mod add;
+pub mod remove;
+pub mod print;
+
+/// Get telemetry servers from the synced patch script
+pub fn servers(config: &Config) -> Option<Vec<String>> {
+    match PatchScript::latest(&config.paths.patch) {
+        Ok(script) if script.servers.is_empty() => {
+            warn(format!("Patch script {:?} doesn't block any servers", script.path));
+
+            None
+        },
+        Ok(script) => Some(script.servers),
+        Err(err) => {
+            error(format!("Failed to read patch script: {}", err));
+
+            None
+        }
+    }
+}
+
+pub struct PatchHosts {
+    args: Vec<Box<dyn Argument>>
+}
+
+impl PatchHosts {
+    pub fn new() -> Box<Self> {
+        Box::new(Self {
+            args: vec![]
+        })
+    }
+}
+
+impl Command for PatchHosts {
+    fn get_name(&self) -> &str {
+        "hosts"
+    }
+
+    fn get_args(&self) -> &Vec<Box<dyn Argument>> {
+        &self.args
+    }
+
+    fn execute(&self, args: Vec<String>, _: Vec<ArgumentValue>) -> bool {
+        let manager = Manager::new(vec![
+            check::PatchHostsCheck::new(),
+            add::PatchHostsAdd::new(),
+            remove::PatchHostsRemove::new(),
+            print::PatchHostsPrint::new()
+        ]);
+    
+        match manager.execute(args[1..].to_vec()) {
+            Ok(_) => (),
+            
+            Err(Error::TooFewArguments) => eprintln!("Arguments required"),
+            Err(Error::CommandNotFound(command)) => eprintln!("Command {} not found", command),
+            Err(Error::ArgumentRequired(argument)) => eprintln!("Argument {} required", argument)
+        }
+
+        true
+    }
+}
@@ -0,0 +1,43 @@
+use commandor::prelude::*;
+
+use crate::lib::config;
+use crate::lib::output::*;
+use crate::lib::hosts;
+use crate::lib::patch::HOSTS_FILE;
+
+pub struct PatchHostsPrint {
+    args: Vec<Box<dyn Argument>>
+}
+
+impl PatchHostsPrint {
+    pub fn new() -> Box<Self> {
+        Box::new(Self {
+            args: vec![]
+        })
+    }
+}
+
+impl Command for PatchHostsPrint {
+    fn get_name(&self) -> &str {
+        "print"
+    }
+
+    fn get_args(&self) -> &Vec<Box<dyn Argument>> {
+        &self.args
+    }
+
+    fn execute(&self, _: Vec<String>, _: Vec<ArgumentValue>) -> bool {
+        let config = config::get().expect("Failed to load config");
+
+        let servers = match super::servers(&config) {
+            Some(servers) => servers,
+            None => return false
+        };
+
+        notice(format!("Lines to be added to {}:", HOSTS_FILE));
+
+        println!("\n{}\n", hosts::entries(&servers));
+
+        true
+    }
+}
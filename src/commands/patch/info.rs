@@ -6,6 +6,7 @@ use anime_game_core::version::ToVersion;
 
 use crate::lib::config;
 use crate::lib::output::*;
+use crate::lib::patch::{self, PatchScript};
 
 pub struct PatchInfo {
     args: Vec<Box<dyn Argument>>
@@ -14,9 +15,105 @@ pub struct PatchInfo {
 impl PatchInfo {
     pub fn new() -> Box<Self> {
         Box::new(Self {
-            args: vec![]
+            args: vec![
+                Flag::new("--verbose", vec!["-v"])
+            ]
         })
     }
+
+    /// Print synced and remote revisions, patched files and hosts file status
+    fn verbose(config: &config::Config, patch_info: &Patch) {
+        let local_revision = patch::local_revision(&config.paths.patch).ok();
+
+        let origin = patch::read_origin(&config.paths.patch);
+
+        let mut output = vec![
+            format!("Patch origin: {}", match &origin {
+                Some(origin) => origin.clone().light_green(),
+                None => "unknown".light_yellow()
+            }),
+            format!("Local revision: {}", match &local_revision {
+                Some(revision) => revision.clone().light_green(),
+                None if origin.is_some() => "unknown (not a git repository)".light_yellow(),
+                None => "not synced".light_red()
+            })
+        ];
+
+        if let Some(revision) = patch::pinned_revision(config, None) {
+            output.push(format!("Pinned revision: {}", revision.light_yellow()));
+        }
+
+        for host in &config.patch.hosts {
+            output.push(format!("- {}: {}", host, match patch::remote_revision(host) {
+                Ok(revision) if Some(&revision) == local_revision.as_ref() => format!("{} (synced)", revision).light_green(),
+                Ok(revision) => revision.light_yellow(),
+                Err(err) => format!("failed to fetch: {}", err).light_red()
+            }));
+        }
+
+        notice(output);
+
+        let version = match patch_info {
+            Patch::NotAvailable => return,
+            Patch::Outdated { current, .. } => *current,
+            patch => patch.to_version().unwrap()
+        };
+
+        if let Patch::Preparation { version, .. } = patch_info {
+            let mut output = vec![format!("Patch for {} game version is being prepared by its developers", version)];
+
+            match Game::new(&config.paths.game).try_get_version() {
+                Ok(installed) if installed == *version => {
+                    output.push(format!("Your game version {} is not supported by the patch yet, so it can't be applied", installed));
+                    output.push(String::from("Wait until it becomes testing or stable before launching the game"));
+                },
+                Ok(installed) => output.push(format!("Your game version is {}. Update the game when the patch is released", installed)),
+                Err(err) => output.push(format!("Failed to get installed game version: {}", err))
+            }
+
+            warn(output);
+        }
+
+        let script = match PatchScript::read(&config.paths.patch, version) {
+            Ok(script) => script,
+            Err(err) => {
+                warn(format!("Failed to read patch script for {} version: {}", version, err));
+
+                return;
+            }
+        };
+
+        let mut output = vec![format!("Patched files ({:?}):", script.path)];
+
+        for file in &script.files {
+            let status = patch::file_status(&config.paths.game, file, &script);
+
+            output.push(format!("- {}: {}", file, match status {
+                patch::FileStatus::Patched => status.to_name().light_green(),
+                patch::FileStatus::Original => status.to_name().light_yellow(),
+                _ => status.to_name().light_red()
+            }));
+        }
+
+        notice(output);
+
+        match patch::blocked_servers(&script.servers) {
+            Ok(servers) => notice({
+                let mut output = vec![format!("Telemetry servers in {}:", patch::HOSTS_FILE)];
+
+                for (server, blocked) in servers {
+                    output.push(format!("- {}: {}", server, if blocked {
+                        "blocked".light_green()
+                    } else {
+                        "not blocked".light_red()
+                    }));
+                }
+
+                output
+            }),
+            Err(err) => warn(format!("Failed to read {}: {}", patch::HOSTS_FILE, err))
+        }
+    }
 }
 
 impl Command for PatchInfo {
@@ -28,14 +125,23 @@ impl Command for PatchInfo {
         &self.args
     }
 
-    fn execute(&self, _: Vec<String>, _: Vec<ArgumentValue>) -> bool {
+    fn execute(&self, _: Vec<String>, values: Vec<ArgumentValue>) -> bool {
         let config = config::get().expect("Failed to load config");
 
+        let mut verbose = false;
+
+        for arg in values {
+            match arg.name.as_str() {
+                "--verbose" => verbose = true,
+                _ => unreachable!()
+            }
+        }
+
         notice("Fetching latest patch info...");
 
-        match Patch::try_fetch(config.patch.hosts, None) {
+        match Patch::try_fetch(config.patch.hosts.clone(), None) {
             Ok(patch) => {
-                match patch {
+                match &patch {
                     Patch::NotAvailable => error("Patch is not available"),
                     Patch::Outdated { current, latest, .. } => {
                         warn(vec![
@@ -56,7 +162,7 @@ impl Command for PatchInfo {
                                     "stable".light_green()
                                 }
                             }, patch.to_version().unwrap()),
-                            format!("Status: {}", match patch.is_applied(config.paths.game) {
+                            format!("Status: {}", match patch.is_applied(&config.paths.game) {
                                 Ok(true) => "applied".light_green(),
                                 Ok(false) => "not applied".light_red(),
                                 Err(err) => format!("failed to check: {}", err).light_red()
@@ -64,6 +170,10 @@ impl Command for PatchInfo {
                         ]);
                     }
                 }
+
+                if verbose {
+                    Self::verbose(&config, &patch);
+                }
             },
             Err(err) => error(format!("Failed to fetch patch info: {}", err))
         }
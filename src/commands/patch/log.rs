@@ -0,0 +1,83 @@
+use commandor::prelude::*;
+use colorful::Colorful;
+
+use crate::lib::config;
+use crate::lib::output::*;
+use crate::lib::patch;
+
+pub struct PatchLog {
+    args: Vec<Box<dyn Argument>>
+}
+
+impl PatchLog {
+    pub fn new() -> Box<Self> {
+        Box::new(Self {
+            args: vec![
+                Default::new("--count", vec!["-n"], true)
+            ]
+        })
+    }
+}
+
+impl Command for PatchLog {
+    fn get_name(&self) -> &str {
+        "log"
+    }
+
+    fn get_args(&self) -> &Vec<Box<dyn Argument>> {
+        &self.args
+    }
+
+    fn execute(&self, _: Vec<String>, values: Vec<ArgumentValue>) -> bool {
+        let config = config::get().expect("Failed to load config");
+
+        let mut count = 10;
+
+        for arg in values {
+            match arg.name.as_str() {
+                "--count" => match arg.value.parse() {
+                    Ok(value) => count = value,
+                    Err(_) => {
+                        error(format!("Wrong revisions number: {}", arg.value));
+
+                        return false;
+                    }
+                },
+                _ => unreachable!()
+            }
+        }
+
+        let current = patch::local_revision(&config.paths.patch).ok();
+
+        let pinned = patch::pinned_revision(&config, None).is_some();
+
+        match patch::log(&config.paths.patch, pinned, count) {
+            Ok(revisions) => notice({
+                let mut output = vec![format!("Latest {} patch revisions:", revisions.len())];
+
+                for revision in revisions {
+                    let mut line = format!("{} {} {}", &revision.commit[..8.min(revision.commit.len())], revision.date, revision.message);
+
+                    if !revision.refs.is_empty() {
+                        line = format!("{} ({})", line, revision.refs);
+                    }
+
+                    if Some(&revision.commit) == current.as_ref() {
+                        output.push(format!("* {}", line).light_green().to_string());
+                    } else {
+                        output.push(format!("  {}", line));
+                    }
+                }
+
+                output
+            }),
+            Err(err) => {
+                error(format!("Failed to read patch repository history: {}. Run `patch sync` first", err));
+
+                return false;
+            }
+        }
+
+        true
+    }
+}
@@ -0,0 +1,88 @@
+use commandor::prelude::*;
+use colorful::Colorful;
+use cli_table::{Cell, Table, print_stdout};
+
+use crate::lib::config;
+use crate::lib::output::*;
+use crate::lib::mirrors;
+
+pub struct PatchMirrorsCheck {
+    args: Vec<Box<dyn Argument>>
+}
+
+impl PatchMirrorsCheck {
+    pub fn new() -> Box<Self> {
+        Box::new(Self {
+            args: vec![]
+        })
+    }
+}
+
+impl Command for PatchMirrorsCheck {
+    fn get_name(&self) -> &str {
+        "check"
+    }
+
+    fn get_args(&self) -> &Vec<Box<dyn Argument>> {
+        &self.args
+    }
+
+    fn execute(&self, _: Vec<String>, _: Vec<ArgumentValue>) -> bool {
+        let config = config::get().expect("Failed to load config");
+
+        if config.patch.hosts.is_empty() {
+            error("Missing patch hosts");
+
+            return false;
+        }
+
+        notice(format!("Checking {} patch hosts...", config.patch.hosts.len()));
+
+        let statuses = mirrors::check(&config.patch.hosts);
+        let latest = mirrors::latest_revision(&statuses, &config.paths.patch);
+        let ranked = mirrors::rank(&statuses, latest.as_ref());
+
+        let mut table = vec![];
+
+        for status in &statuses {
+            table.push(vec![
+                if ranked.first().map(|best| best.host == status.host).unwrap_or(false) {
+                    "[*]".light_green().cell()
+                } else {
+                    "[ ]".cell()
+                },
+                status.host.clone().cell(),
+                match status.latency_ms {
+                    Some(latency) => format!("{} ms", latency).cell(),
+                    None => "unreachable".light_red().cell()
+                },
+                match (&status.revision, &status.error) {
+                    (Some(revision), _) if Some(revision) == latest.as_ref() => revision.clone().light_green().cell(),
+                    (Some(revision), _) if latest.is_none() => format!("{} (unknown)", revision).light_yellow().cell(),
+                    (Some(revision), _) => format!("{} (outdated)", revision).light_yellow().cell(),
+                    (None, Some(err)) => err.clone().light_red().cell(),
+                    (None, None) => "".cell()
+                }
+            ]);
+        }
+
+        print_stdout(table.table()).expect("Failed to print hosts table");
+
+        println!();
+
+        match ranked.first() {
+            Some(best) if latest.is_some() => notice(format!("The healthiest up to date host: {}", best.host)),
+            Some(best) => warn(vec![
+                String::from("Hosts have different revisions and the newest one can't be found"),
+                format!("The fastest host: {}", best.host)
+            ]),
+            None => {
+                error("All the patch hosts are unreachable");
+
+                return false;
+            }
+        }
+
+        true
+    }
+}
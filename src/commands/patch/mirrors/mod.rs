@@ -0,0 +1,41 @@
+use commandor::prelude::*;
+
+pub mod check;
+
+pub struct PatchMirrors {
+    args: Vec<Box<dyn Argument>>
+}
+
+impl PatchMirrors {
+    pub fn new() -> Box<Self> {
+        Box::new(Self {
+            args: vec![]
+        })
+    }
+}
+
+impl Command for PatchMirrors {
+    fn get_name(&self) -> &str {
+        "mirrors"
+    }
+
+    fn get_args(&self) -> &Vec<Box<dyn Argument>> {
+        &self.args
+    }
+
+    fn execute(&self, args: Vec<String>, _: Vec<ArgumentValue>) -> bool {
+        let manager = Manager::new(vec![
+            check::PatchMirrorsCheck::new()
+        ]);
+    
+        match manager.execute(args[1..].to_vec()) {
+            Ok(_) => (),
+            
+            Err(Error::TooFewArguments) => eprintln!("Arguments required"),
+            Err(Error::CommandNotFound(command)) => eprintln!("Command {} not found", command),
+            Err(Error::ArgumentRequired(argument)) => eprintln!("Argument {} required", argument)
+        }
+
+        true
+    }
+}
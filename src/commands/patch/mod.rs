@@ -4,6 +4,9 @@ pub mod info;
 pub mod sync;
 pub mod apply;
 pub mod revert;
+pub mod hosts;
+pub mod mirrors;
+pub mod log;
 
 pub struct Patch {
     args: Vec<Box<dyn Argument>>
@@ -31,7 +34,10 @@ impl Command for Patch {
             info::PatchInfo::new(),
             sync::PatchSync::new(),
             apply::PatchApply::new(),
-            revert::PatchRevert::new()
+            revert::PatchRevert::new(),
+            hosts::PatchHosts::new(),
+            mirrors::PatchMirrors::new(),
+            log::PatchLog::new()
         ]);
     
         match manager.execute(args[1..].to_vec()) {
@@ -5,6 +5,9 @@ use anime_game_core::prelude::genshin::*;
 
 use crate::lib::config;
 use crate::lib::output::*;
+use crate::lib::lock::Lock;
+use crate::lib::patch;
+use crate::lib::patch_backup;
 
 pub struct PatchRevert {
     args: Vec<Box<dyn Argument>>
@@ -14,7 +17,8 @@ impl PatchRevert {
     pub fn new() -> Box<Self> {
         Box::new(Self {
             args: vec![
-                Flag::new("--force", vec!["-f"])
+                Flag::new("--force", vec!["-f"]),
+                Flag::with_name("--wait")
             ]
         })
     }
@@ -40,16 +44,63 @@ impl Command for PatchRevert {
 
         let mut force_revert = false;
 
+        let mut wait = false;
+
         for arg in args {
-            match arg.value.as_str() {
+            match arg.name.as_str() {
                 "--force" => force_revert = true,
+                "--wait" => wait = true,
                 _ => unreachable!()
             }
         }
 
+        let _lock = match Lock::acquire(&config.paths.game, wait) {
+            Ok(lock) => lock,
+            Err(err) => {
+                error(err.to_string());
+
+                return false;
+            }
+        };
+
+        // Backup made by `patch apply` doesn't rely on files timestamps
+        match patch_backup::find(&config.paths.game) {
+            Ok(Some((backup, manifest))) => {
+                notice(format!("Restoring original files from {} patch backup...", manifest.version));
+
+                match patch_backup::restore(&config.paths.game, backup) {
+                    Ok(files) => {
+                        notice({
+                            let mut output = vec![format!("Patch reverted successfully. Restored {} files", files.len())];
+
+                            for file in files {
+                                output.push(format!("- {:?}", file));
+                            }
+
+                            output
+                        });
+
+                        // Backup has only game files, so lines added to the hosts file by the patch are kept
+                        if let Ok(script) = patch::PatchScript::latest(&config.paths.patch) {
+                            if let Ok(servers) = patch::blocked_servers(&script.servers) {
+                                if servers.iter().any(|(_, blocked)| *blocked) {
+                                    notice(format!("Telemetry servers are still blocked in {}. Run {} to unblock them", patch::HOSTS_FILE, "patch hosts remove".light_yellow()));
+                                }
+                            }
+                        }
+
+                        return true;
+                    },
+                    Err(err) => warn(format!("Failed to restore patch backup: {}. Trying to revert the patch by its script", err))
+                }
+            },
+            Ok(None) => (),
+            Err(err) => warn(format!("Failed to read patch backup: {}", err))
+        }
+
         let applier = PatchApplier::new(&config.paths.patch);
 
-        match applier.is_sync(&config.patch.hosts) {
+        match patch::is_synced(&config) {
             Ok(true) => {
                 notice("Fetching latest patch info...");
 
@@ -4,6 +4,9 @@ use anime_game_core::prelude::genshin::*;
 
 use crate::lib::config;
 use crate::lib::output::*;
+use crate::lib::patch;
+use crate::lib::temp;
+use crate::lib::mirrors;
 
 pub struct PatchSync {
     args: Vec<Box<dyn Argument>>
@@ -13,10 +16,54 @@ impl PatchSync {
     pub fn new() -> Box<Self> {
         Box::new(Self {
             args: vec![
-                Flag::new("--recursive", vec!["-r"])
+                Flag::new("--recursive", vec!["-r"]),
+                Default::new("--from", vec![], true),
+                Flag::with_name("--fastest"),
+                Default::new("--revision", vec![], true)
             ]
         })
     }
+
+    /// Sync the patch from a local folder, archive or git bundle
+    fn sync_from(config: &config::Config, source: &str) -> bool {
+        let source = match std::path::Path::new(source).canonicalize() {
+            Ok(source) => source,
+            Err(err) => {
+                error(format!("Failed to find {}: {}", source, err));
+
+                return false;
+            }
+        };
+
+        let staging_folder = match temp::staging_folder(None) {
+            Ok(folder) => folder,
+            Err(err) => {
+                error(format!("Failed to create staging folder: {}", err));
+
+                return false;
+            }
+        };
+
+        notice(format!("Syncing patch from {:?}...", source));
+
+        let result = patch::sync_from(&source, &config.paths.patch, &staging_folder)
+            .and_then(|_| patch::write_origin(&config.paths.patch, &source.to_string_lossy()).map_err(Into::into));
+
+        temp::remove_staging_folder(None);
+
+        match result {
+            Ok(()) => {
+                notice("Patch successfully synced");
+
+                true
+            },
+            Err(err) => {
+                error(format!("Failed to sync patch: {}", err));
+
+                false
+            }
+        }
+    }
 }
 
 impl Command for PatchSync {
@@ -31,44 +78,92 @@ impl Command for PatchSync {
     fn execute(&self, _: Vec<String>, args: Vec<ArgumentValue>) -> bool {
         let config = config::get().expect("Failed to load config");
 
+        // Try to sync with all available repos until it doesn't succeed
+        let mut recursive = false;
+        let mut from = None;
+        let mut fastest = false;
+        let mut revision = None;
+
+        for arg in args {
+            match arg.name.as_str() {
+                "--revision" => revision = Some(arg.value),
+                "--recursive" => recursive = true,
+                "--from" => from = Some(arg.value),
+                "--fastest" => fastest = true,
+                _ => unreachable!()
+            }
+        }
+
+        if let Some(source) = from {
+            return Self::sync_from(&config, &source);
+        }
+
         if config.patch.hosts.is_empty() {
             error("Missing patch hosts");
 
             return false;
         }
 
-        // Try to sync with all available repos until it doesn't succeed
-        let mut recursive = false;
+        let applier = PatchApplier::new(&config.paths.patch);
+        let revision = patch::pinned_revision(&config, revision);
 
-        for arg in args {
-            match arg.value.as_str() {
-                "--recursive" => recursive = true,
-                _ => unreachable!()
+        // Pinned revision can already be in the local repository. Branches are synced to get their new commits
+        if let Some(revision) = &revision {
+            if patch::remote_branch(&config.patch.hosts, revision).is_none() && patch::checkout(&config.paths.patch, revision).is_ok() {
+                notice(format!("Patch is pinned to {}", revision));
+
+                return true;
             }
         }
 
-        let patch = PatchApplier::new(config.paths.patch);
+        let synced = match revision {
+            Some(_) => Ok(false),
+            None => applier.is_sync(&config.patch.hosts)
+        };
 
-        match patch.is_sync(&config.patch.hosts) {
+        match synced {
             Ok(true) => notice("Patch is already synced"),
             Ok(false) => {
-                let hosts = if recursive { &config.patch.hosts } else { &config.patch.hosts[..1] };
+                let mut hosts = config.patch.hosts.clone();
 
-                notice("Syncing patch...");
+                // Sort hosts from the healthiest one
+                if fastest {
+                    let statuses = mirrors::get(&config.patch.hosts, config.patch.mirrors_cache_ttl);
+                    let latest = mirrors::latest_revision(&statuses, &config.paths.patch);
+                    let ranked = mirrors::rank(&statuses, latest.as_ref());
 
-                for host in hosts {
-                    match patch.sync(host) {
-                        Ok(true) => {
-                            notice("Patch successfully synced");
-    
-                            return true;
-                        },
-                        Ok(false) => warn(format!("Failed to sync repo {host}")),
-                        Err(err) => warn(format!("Failed to sync repo {host}: {err}"))
+                    if ranked.is_empty() {
+                        error("All the patch hosts are unreachable");
+
+                        return false;
+                    }
+
+                    hosts = ranked.into_iter().map(|mirror| mirror.host).collect();
+
+                    if latest.is_some() {
+                        notice(format!("Using the healthiest host: {}", hosts[0]));
+                    } else {
+                        warn(format!("Hosts have different revisions and the newest one can't be found. Using the fastest host: {}", hosts[0]));
                     }
                 }
 
-                error("Failed to sync patch");
+                if !recursive {
+                    hosts.truncate(1);
+                }
+
+                notice("Syncing patch...");
+
+                match patch::sync(&config, &hosts, revision.as_deref()) {
+                    Ok(_) => match &revision {
+                        Some(revision) => notice(format!("Patch successfully synced and pinned to {revision}")),
+                        None => notice("Patch successfully synced")
+                    },
+                    Err(err) => {
+                        error(err.to_string());
+
+                        return false;
+                    }
+                }
             },
             Err(err) => error(format!("Failed to check patch folder: {}", err))
         }
@@ -0,0 +1,77 @@
+use commandor::prelude::*;
+
+use anime_game_core::genshin::prelude::*;
+use anime_game_core::genshin::repairer::{try_get_integrity_files, try_get_voice_integrity_files};
+
+use crate::lib::manifest::{self, ManifestFile};
+use crate::lib::output::*;
+
+pub struct RepairManifestExport {
+    args: Vec<Box<dyn Argument>>
+}
+
+impl RepairManifestExport {
+    pub fn new() -> Box<Self> {
+        Box::new(Self {
+            args: vec![]
+        })
+    }
+}
+
+impl Command for RepairManifestExport {
+    fn get_name(&self) -> &str {
+        "export"
+    }
+
+    fn get_args(&self) -> &Vec<Box<dyn Argument>> {
+        &self.args
+    }
+
+    fn execute(&self, args: Vec<String>, _: Vec<ArgumentValue>) -> bool {
+        let path = match args.get(1) {
+            Some(path) => path,
+            None => {
+                error("You didn't specify the manifest file");
+
+                return false;
+            }
+        };
+
+        notice("Fetching game integrity files...");
+
+        let mut files = match try_get_integrity_files(None) {
+            Ok(files) => files.into_iter().map(|file| ManifestFile {
+                package: Some(manifest::GAME_PACKAGE.to_string()),
+                file
+            }).collect::<Vec<ManifestFile>>(),
+            Err(err) => {
+                error(format!("Failed to get game integrity files: {}", err));
+
+                return false;
+            }
+        };
+
+        for locale in VoiceLocale::list() {
+            notice(format!("Fetching {} package integrity files...", locale.to_name()));
+
+            match try_get_voice_integrity_files(*locale, None) {
+                Ok(voice_files) => files.extend(voice_files.into_iter().map(|file| ManifestFile {
+                    package: Some(locale.to_code().to_string()),
+                    file
+                })),
+                Err(err) => {
+                    error(format!("Failed to get {} package integrity files: {}", locale.to_name(), err));
+
+                    return false;
+                }
+            }
+        }
+
+        match manifest::write(path, &files) {
+            Ok(_) => notice(format!("Saved {} integrity files to {}", files.len(), path)),
+            Err(err) => error(format!("Failed to save manifest: {}", err))
+        }
+
+        true
+    }
+}
@@ -0,0 +1,131 @@
+use std::path::{Path, PathBuf};
+
+use commandor::prelude::*;
+
+use anime_game_core::repairer::IntegrityFile;
+
+use crate::lib::{hash, orphans, list_files, positional_args, format_size};
+use crate::lib::manifest::{self, ManifestFile};
+use crate::lib::command_traits::repair::*;
+use crate::lib::output::*;
+
+pub struct RepairManifestGenerate {
+    args: Vec<Box<dyn Argument>>
+}
+
+impl RepairManifestGenerate {
+    pub fn new() -> Box<Self> {
+        Box::new(Self {
+            args: vec![
+                Default::new("--output", vec!["-o"], true),
+                Default::new("--threads", vec!["-t"], true)
+            ]
+        })
+    }
+}
+
+impl Command for RepairManifestGenerate {
+    fn get_name(&self) -> &str {
+        "generate"
+    }
+
+    fn get_args(&self) -> &Vec<Box<dyn Argument>> {
+        &self.args
+    }
+
+    fn execute(&self, args: Vec<String>, values: Vec<ArgumentValue>) -> bool {
+        let game_path = match positional_args(&args[1..], &["--output", "-o", "--threads", "-t"]).first() {
+            Some(path) => path.clone(),
+            None => {
+                error("You didn't specify the game path");
+
+                return false;
+            }
+        };
+
+        let mut output = String::from("pkg_version");
+        let mut threads = RepairFilesConfig::default().verify_threads;
+
+        for arg in values {
+            match arg.name.as_str() {
+                "--output" => output = arg.value,
+                "--threads" => threads = arg.value.parse::<usize>().expect("Wrong threads num"),
+                _ => unreachable!()
+            }
+        }
+
+        notice(format!("Listing files in {}...", game_path));
+
+        // Configs, logs and other local files are not the part of the installation
+        let files = match list_files(&game_path) {
+            Ok(files) => files.into_iter()
+                .filter(|path| !orphans::is_allowed(path))
+                .map(|path| {
+                    let size = std::fs::metadata(Path::new(&game_path).join(&path))
+                        .map(|metadata| metadata.len())
+                        .unwrap_or(0);
+
+                    (path, size)
+                })
+                .collect::<Vec<(PathBuf, u64)>>(),
+            Err(err) => {
+                error(format!("Failed to list game files: {}", err));
+
+                return false;
+            }
+        };
+
+        let total_size = files.iter().map(|(_, size)| size).sum::<u64>();
+
+        notice(format!("Hashing {} files ({} GB) in {} threads:\n", files.len(), format_size(total_size), threads.min(files.len())));
+
+        let game_path_ref = game_path.clone();
+
+        let results = run_threads(files, threads, |(_, size)| *size, move |(path, size)| {
+            Some(match hash::md5(Path::new(&game_path_ref).join(&path)) {
+                Ok(md5) => Ok(ManifestFile {
+                    package: None,
+                    file: IntegrityFile {
+                        path,
+                        md5,
+                        size,
+                        base_url: String::new()
+                    }
+                }),
+                Err(err) => Err((path, err))
+            })
+        });
+
+        let mut files = Vec::new();
+        let mut failed = false;
+
+        for result in results {
+            match result {
+                Ok(file) => files.push(file),
+                Err((path, err)) => {
+                    error(format!("Failed to hash {:?}: {}", path, err));
+
+                    failed = true;
+                }
+            }
+        }
+
+        println!();
+
+        if failed {
+            error("Manifest wasn't saved because some files couldn't be hashed");
+
+            return false;
+        }
+
+        // Keep manifests reproducible
+        files.sort_by(|a, b| a.file.path.cmp(&b.file.path));
+
+        match manifest::write(&output, &files) {
+            Ok(_) => notice(format!("Saved {} integrity files to {}", files.len(), output)),
+            Err(err) => error(format!("Failed to save manifest: {}", err))
+        }
+
+        true
+    }
+}
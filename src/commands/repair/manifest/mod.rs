@@ -0,0 +1,43 @@
+use commandor::prelude::*;
+
+pub mod export;
+pub mod generate;
+
+pub struct RepairManifest {
+    args: Vec<Box<dyn Argument>>
+}
+
+impl RepairManifest {
+    pub fn new() -> Box<Self> {
+        Box::new(Self {
+            args: vec![]
+        })
+    }
+}
+
+impl Command for RepairManifest {
+    fn get_name(&self) -> &str {
+        "manifest"
+    }
+
+    fn get_args(&self) -> &Vec<Box<dyn Argument>> {
+        &self.args
+    }
+
+    fn execute(&self, args: Vec<String>, _: Vec<ArgumentValue>) -> bool {
+        let manager = Manager::new(vec![
+            export::RepairManifestExport::new(),
+            generate::RepairManifestGenerate::new()
+        ]);
+    
+        match manager.execute(args[1..].to_vec()) {
+            Ok(_) => (),
+            
+            Err(Error::TooFewArguments) => eprintln!("Arguments required"),
+            Err(Error::CommandNotFound(command)) => eprintln!("Command {} not found", command),
+            Err(Error::ArgumentRequired(argument)) => eprintln!("Argument {} required", argument)
+        }
+
+        true
+    }
+}
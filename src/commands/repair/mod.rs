@@ -0,0 +1,45 @@
+use commandor::prelude::*;
+
+pub mod restore;
+pub mod manifest;
+pub mod schedule;
+
+pub struct Repair {
+    args: Vec<Box<dyn Argument>>
+}
+
+impl Repair {
+    pub fn new() -> Box<Self> {
+        Box::new(Self {
+            args: vec![]
+        })
+    }
+}
+
+impl Command for Repair {
+    fn get_name(&self) -> &str {
+        "repair"
+    }
+
+    fn get_args(&self) -> &Vec<Box<dyn Argument>> {
+        &self.args
+    }
+
+    fn execute(&self, args: Vec<String>, _: Vec<ArgumentValue>) -> bool {
+        let manager = Manager::new(vec![
+            restore::RepairRestore::new(),
+            manifest::RepairManifest::new(),
+            schedule::RepairSchedule::new()
+        ]);
+    
+        match manager.execute(args[1..].to_vec()) {
+            Ok(_) => (),
+            
+            Err(Error::TooFewArguments) => eprintln!("Arguments required"),
+            Err(Error::CommandNotFound(command)) => eprintln!("Command {} not found", command),
+            Err(Error::ArgumentRequired(argument)) => eprintln!("Argument {} required", argument)
+        }
+
+        true
+    }
+}
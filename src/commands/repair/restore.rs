@@ -0,0 +1,113 @@
+use commandor::prelude::*;
+
+use crate::lib::quarantine;
+use crate::lib::output::*;
+use crate::lib::lock::Lock;
+use crate::lib::positional_args;
+
+pub struct RepairRestore {
+    args: Vec<Box<dyn Argument>>
+}
+
+impl RepairRestore {
+    pub fn new() -> Box<Self> {
+        Box::new(Self {
+            args: vec![
+                Flag::with_name("--wait")
+            ]
+        })
+    }
+}
+
+impl Command for RepairRestore {
+    fn get_name(&self) -> &str {
+        "restore"
+    }
+
+    fn get_args(&self) -> &Vec<Box<dyn Argument>> {
+        &self.args
+    }
+
+    fn execute(&self, args: Vec<String>, values: Vec<ArgumentValue>) -> bool {
+        let mut wait = false;
+
+        for arg in &values {
+            match arg.name.as_str() {
+                "--wait" => wait = true,
+                _ => unreachable!()
+            }
+        }
+
+        let session = match positional_args(&args[1..], &[]).first() {
+            Some(session) => session.clone(),
+            None => {
+                error("You didn't specify the quarantine session folder");
+
+                return false;
+            }
+        };
+
+        let manifest = match quarantine::Manifest::read(&session) {
+            Ok(manifest) => manifest,
+            Err(err) => {
+                error(format!("Failed to read quarantine session: {}", err));
+
+                return false;
+            }
+        };
+
+        notice(format!("Restoring {} files quarantined at {} to {}...", manifest.files.len(), manifest.created, manifest.game_path));
+
+        let _lock = match Lock::acquire(&manifest.game_path, wait) {
+            Ok(lock) => lock,
+            Err(err) => {
+                error(err.to_string());
+
+                return false;
+            }
+        };
+
+        match quarantine::restore(&session) {
+            Ok(result) => {
+                let mut output = vec![format!("Restored {} files", result.restored.len())];
+
+                for file in &result.restored {
+                    output.push(format!("- {:?}", file));
+                }
+
+                notice(output);
+
+                if !result.missing.is_empty() {
+                    let mut output = vec![format!("{} files are not in the session anymore and were skipped", result.missing.len())];
+
+                    for file in &result.missing {
+                        output.push(format!("- {:?}", file));
+                    }
+
+                    warn(output);
+                }
+
+                if !result.failed.is_empty() {
+                    let mut output = vec![format!("Failed to restore {} files", result.failed.len())];
+
+                    for (file, err) in &result.failed {
+                        output.push(format!("- {:?}: {}", file, err));
+                    }
+
+                    output.push(String::from("Run the command again to restore the rest of the session"));
+
+                    error(output);
+
+                    return false;
+                }
+            },
+            Err(err) => {
+                error(format!("Failed to restore quarantined files: {}", err));
+
+                return false;
+            }
+        }
+
+        true
+    }
+}
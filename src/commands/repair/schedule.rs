@@ -0,0 +1,153 @@
+use std::path::PathBuf;
+use std::process::Command as ProcessCommand;
+
+use commandor::prelude::*;
+
+use crate::lib::output::*;
+
+/// Scheduled verifications: name, systemd calendar event and repair arguments
+const SCHEDULES: &[(&str, &str, &str)] = &[
+    ("anime-game-cli-verify-fast", "daily", "game repair --all --verify --fast"),
+    ("anime-game-cli-verify-full", "weekly", "game repair --all --verify --threads auto")
+];
+
+pub struct RepairSchedule {
+    args: Vec<Box<dyn Argument>>
+}
+
+impl RepairSchedule {
+    pub fn new() -> Box<Self> {
+        Box::new(Self {
+            args: vec![
+                Flag::with_name("--print"), // Print unit files instead of installing them
+                Flag::with_name("--remove") // Disable timers and remove unit files
+            ]
+        })
+    }
+}
+
+/// Get systemd user units folder
+fn units_folder() -> Option<PathBuf> {
+    match std::env::var("XDG_CONFIG_HOME") {
+        Ok(config) if !config.is_empty() => Some(PathBuf::from(config).join("systemd/user")),
+        _ => std::env::var("HOME").ok().map(|home| PathBuf::from(home).join(".config/systemd/user"))
+    }
+}
+
+/// Run `systemctl --user` with given arguments
+fn systemctl(args: &[&str]) -> bool {
+    match ProcessCommand::new("systemctl").arg("--user").args(args).status() {
+        Ok(status) => status.success(),
+        Err(_) => false
+    }
+}
+
+/// Generate service and timer units contents
+fn units(calendar: &str, arguments: &str) -> anyhow::Result<(String, String)> {
+    // Config and verification state are stored in the current folder
+    let working_dir = std::env::current_dir()?;
+    let executable = std::env::current_exe()?;
+
+    let service = format!(
+        "[Unit]\nDescription=Anime Game files verification ({arguments})\n\n[Service]\nType=oneshot\nWorkingDirectory={}\nExecStart=\"{}\" {arguments}\n",
+        working_dir.to_string_lossy(),
+        executable.to_string_lossy()
+    );
+
+    let timer = format!(
+        "[Unit]\nDescription=Anime Game files verification ({calendar})\n\n[Timer]\nOnCalendar={calendar}\nPersistent=true\n\n[Install]\nWantedBy=timers.target\n"
+    );
+
+    Ok((service, timer))
+}
+
+impl Command for RepairSchedule {
+    fn get_name(&self) -> &str {
+        "schedule"
+    }
+
+    fn get_args(&self) -> &Vec<Box<dyn Argument>> {
+        &self.args
+    }
+
+    fn execute(&self, _: Vec<String>, values: Vec<ArgumentValue>) -> bool {
+        let mut print = false;
+        let mut remove = false;
+
+        for arg in values {
+            match arg.name.as_str() {
+                "--print" => print = true,
+                "--remove" => remove = true,
+                _ => unreachable!()
+            }
+        }
+
+        let folder = match units_folder() {
+            Some(folder) => folder,
+            None => {
+                error("Failed to find systemd user units folder");
+
+                return false;
+            }
+        };
+
+        if remove {
+            for (name, _, _) in SCHEDULES {
+                systemctl(&["disable", "--now", &format!("{name}.timer")]);
+
+                for unit in [format!("{name}.service"), format!("{name}.timer")] {
+                    if let Err(err) = std::fs::remove_file(folder.join(&unit)) {
+                        warn(format!("Failed to remove {}: {}", unit, err));
+                    }
+                }
+            }
+
+            systemctl(&["daemon-reload"]);
+
+            notice("Scheduled verification removed");
+
+            return true;
+        }
+
+        for (name, calendar, arguments) in SCHEDULES {
+            let (service, timer) = match units(calendar, arguments) {
+                Ok(units) => units,
+                Err(err) => {
+                    error(format!("Failed to generate unit files: {}", err));
+
+                    return false;
+                }
+            };
+
+            if print {
+                println!("# {name}.service\n{service}\n# {name}.timer\n{timer}");
+
+                continue;
+            }
+
+            let result = std::fs::create_dir_all(&folder)
+                .and_then(|_| std::fs::write(folder.join(format!("{name}.service")), service))
+                .and_then(|_| std::fs::write(folder.join(format!("{name}.timer")), timer));
+
+            if let Err(err) = result {
+                error(format!("Failed to save {} units: {}", name, err));
+
+                return false;
+            }
+        }
+
+        if !print {
+            systemctl(&["daemon-reload"]);
+
+            for (name, calendar, _) in SCHEDULES {
+                if systemctl(&["enable", "--now", &format!("{name}.timer")]) {
+                    notice(format!("Enabled {} verification ({name}.timer)", calendar));
+                } else {
+                    error(format!("Failed to enable {name}.timer"));
+                }
+            }
+        }
+
+        true
+    }
+}
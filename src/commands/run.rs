@@ -1,9 +1,16 @@
 use std::process::Command as ProcessCommand;
 
 use commandor::prelude::*;
+use colorful::Colorful;
+
+use anime_game_core::prelude::genshin::*;
 
 use crate::lib::config;
 use crate::lib::output::*;
+use crate::lib::temp;
+use crate::lib::hosts;
+use crate::lib::patch::{self, PatchScript, HOSTS_FILE};
+use crate::lib::lock::Lock;
 
 pub struct Run {
     args: Vec<Box<dyn Argument>>
@@ -12,9 +19,132 @@ pub struct Run {
 impl Run {
     pub fn new() -> Box<Self> {
         Box::new(Self {
-            args: vec![]
+            args: vec![
+                Flag::with_name("--ignore-patch"),
+                Flag::with_name("--wait")
+            ]
         })
     }
+
+    /// Check if the game can be launched safely with the current patch state
+    ///
+    /// Returns explanation why it can't be launched
+    fn check_patch(config: &config::Config, patch_info: Result<&Patch, &anyhow::Error>) -> Result<(), Vec<String>> {
+        let patch_info = match patch_info {
+            Ok(patch_info) => patch_info,
+
+            // Patch hosts can be unreachable when there's no internet connection
+            Err(err) => return match patch::is_applied_locally(config) {
+                Ok(true) => {
+                    warn(vec![
+                        format!("Failed to fetch patch info: {}", err),
+                        String::from("Patch status is not known, but the patch is applied to the game files")
+                    ]);
+
+                    Ok(())
+                },
+                Ok(false) => Err(vec![
+                    format!("Failed to fetch patch info: {}", err),
+                    String::from("Patch is not applied to the game files")
+                ]),
+                Err(local_err) => Err(vec![
+                    format!("Failed to fetch patch info: {}", err),
+                    format!("Failed to check game files: {}", local_err)
+                ])
+            }
+        };
+
+        match patch_info {
+            Patch::NotAvailable => return Err(vec![
+                String::from("Patch is not available"),
+                String::from("Your game version is not supported by the patch")
+            ]),
+            Patch::Outdated { current, latest, .. } => return Err(vec![
+                format!("Patch is outdated ({} -> {})", current, latest),
+                String::from("The game was updated, but the patch for the new version is not released yet")
+            ]),
+            Patch::Preparation { version, .. } => return Err(vec![
+                format!("Patch for {} is in preparation state", version),
+                String::from("Patch developers are working on the new game version, and it can't be applied yet")
+            ]),
+            Patch::Testing { version, .. } => warn(format!("Patch for {} is in testing stage", version)),
+            Patch::Available { .. } => ()
+        }
+
+        match patch_info.is_applied(&config.paths.game) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(vec![
+                String::from("Patch is not applied"),
+                format!("Run {} first", "patch apply".light_yellow())
+            ]),
+            Err(err) => Err(vec![format!("Failed to check game patching status: {}", err)])
+        }
+    }
+
+    /// Sync and apply the patch if it's not applied
+    ///
+    /// The game is locked only if the patch should be applied
+    fn auto_apply(config: &config::Config, patch_info: &Patch, wait: bool) -> anyhow::Result<()> {
+        if patch_info.is_applied(&config.paths.game)? {
+            return Ok(());
+        }
+
+        let _lock = Lock::acquire(&config.paths.game, wait)?;
+
+        patch::auto_apply(config, patch_info)
+    }
+
+    /// Prepare wine command which will be run in a user namespace
+    /// with the system hosts file replaced by the one blocking telemetry servers
+    ///
+    /// Wine resolves hostnames with the system resolver which reads `/etc/hosts` only,
+    /// so there's no DLL override or environment variable to block servers with.
+    /// The hosts file is bind-mounted in a separate mount namespace instead, and the game
+    /// is run in a nested user namespace with the current user's ids, not as namespace root
+    fn isolated_hosts(config: &config::Config) -> anyhow::Result<ProcessCommand> {
+        let script = PatchScript::latest(&config.paths.patch)?;
+
+        let system_hosts = std::fs::read_to_string(HOSTS_FILE).unwrap_or_default();
+        let path = temp::staging_folder(None)?.join("hosts");
+
+        std::fs::write(&path, hosts::with_entries(&system_hosts, &script.servers))?;
+
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+
+        let mut command = ProcessCommand::new("unshare");
+
+        command.args(["--user", "--map-root-user", "--mount", "sh", "-c"])
+            .arg(format!("mount --bind \"$0\" {HOSTS_FILE} && exec unshare --user --map-user={uid} --map-group={gid} \"$@\""))
+            .arg(path)
+            .arg(&config.wine.executable);
+
+        Ok(command)
+    }
+
+    /// Check if unprivileged user and mount namespaces needed by `isolated_hosts` can be created
+    ///
+    /// Returns the reason why they can't be
+    fn check_namespaces() -> Result<(), String> {
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+
+        let output = ProcessCommand::new("unshare")
+            .args(["--user", "--map-root-user", "--mount", "sh", "-c"])
+            .arg(format!("exec unshare --user --map-user={uid} --map-group={gid} true"))
+            .output();
+
+        match output {
+            Ok(output) if output.status.success() => Ok(()),
+            Ok(output) => Err(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+            Err(err) => Err(format!("failed to run unshare: {}", err))
+        }
+    }
+
+    /// Check if all the telemetry servers are blocked in the system hosts file
+    fn blocked_in_system(config: &config::Config) -> anyhow::Result<bool> {
+        let script = PatchScript::latest(&config.paths.patch)?;
+
+        Ok(patch::blocked_servers(&script.servers)?.iter().all(|(_, blocked)| *blocked))
+    }
 }
 
 impl Command for Run {
@@ -26,10 +156,91 @@ impl Command for Run {
         &self.args
     }
 
-    fn execute(&self, _: Vec<String>, _: Vec<ArgumentValue>) -> bool {
+    fn execute(&self, _: Vec<String>, values: Vec<ArgumentValue>) -> bool {
         let config = config::get().expect("Failed to load config");
 
-        let child = ProcessCommand::new(config.wine.executable)
+        let mut ignore_patch = false;
+        let mut wait = false;
+
+        for arg in values {
+            match arg.name.as_str() {
+                "--ignore-patch" => ignore_patch = true,
+                "--wait" => wait = true,
+                _ => unreachable!()
+            }
+        }
+
+        if ignore_patch {
+            warn("Patch status is not checked. Launching the game without the patch is not safe");
+        }
+
+        else {
+            let patch_info = Patch::try_fetch(config.patch.hosts.clone(), None);
+
+            // Failed fetching is handled by the patch status check
+            if config.patch.auto_apply {
+                if let Ok(patch_info) = &patch_info {
+                    if let Err(err) = Self::auto_apply(&config, patch_info, wait) {
+                        error(vec![
+                            format!("Failed to apply the patch automatically: {}", err),
+                            String::from("The game won't be launched without the patch")
+                        ]);
+
+                        return false;
+                    }
+                }
+            }
+
+            if let Err(mut output) = Self::check_patch(&config, patch_info.as_ref()) {
+                output.push(format!("The game won't be launched. Use {} to launch it anyway", "--ignore-patch".light_yellow()));
+
+                error(output);
+
+                return false;
+            }
+        }
+
+        // User namespaces can be disabled or restricted by the system
+        let isolated_hosts = config.patch.isolated_hosts && match Self::check_namespaces() {
+            Ok(()) => true,
+            Err(reason) => match Self::blocked_in_system(&config) {
+                Ok(true) => {
+                    warn(vec![
+                        format!("Failed to create user namespace: {}", reason),
+                        format!("Telemetry servers are blocked in {} so the game will be launched without isolated hosts", HOSTS_FILE)
+                    ]);
+
+                    false
+                },
+                result => {
+                    error(vec![
+                        format!("Failed to create user namespace: {}", reason),
+                        match result {
+                            Err(err) => format!("Failed to check {}: {}", HOSTS_FILE, err),
+                            _ => format!("Telemetry servers are not blocked in {}", HOSTS_FILE)
+                        },
+                        format!("Run {} or enable unprivileged user namespaces to launch the game", "patch hosts add".light_yellow())
+                    ]);
+
+                    return false;
+                }
+            }
+        };
+
+        let mut command = if isolated_hosts {
+            match Self::isolated_hosts(&config) {
+                Ok(command) => command,
+                Err(err) => {
+                    error(format!("Failed to prepare hosts file for the game: {}", err));
+
+                    return false;
+                }
+            }
+        } else {
+            ProcessCommand::new(&config.wine.executable)
+        };
+
+        let child = command
             .envs(config.wine.environment)
             .env("WINEPREFIX", &config.wine.prefix)
             .current_dir(config.paths.game)
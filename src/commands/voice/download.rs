@@ -1,4 +1,5 @@
 use std::collections::HashSet;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
 use commandor::prelude::*;
@@ -8,7 +9,10 @@ use anime_game_core::genshin::prelude::*;
 
 use crate::lib::config;
 use crate::lib::output::*;
-use crate::lib::format_size;
+use crate::lib::space;
+use crate::lib::temp;
+use crate::lib::lock::Lock;
+use crate::lib::{format_size, positional_args};
 
 pub struct VoiceDownload {
     args: Vec<Box<dyn Argument>>
@@ -17,7 +21,11 @@ pub struct VoiceDownload {
 impl VoiceDownload {
     pub fn new() -> Box<Self> {
         Box::new(Self {
-            args: vec![]
+            args: vec![
+                Flag::with_name("--ignore-space-check"),
+                Default::new("--temp-dir", vec![], true),
+                Flag::with_name("--wait")
+            ]
         })
     }
 }
@@ -31,9 +39,31 @@ impl Command for VoiceDownload {
         &self.args
     }
 
-    fn execute(&self, args: Vec<String>, _: Vec<ArgumentValue>) -> bool {
+    fn execute(&self, args: Vec<String>, values: Vec<ArgumentValue>) -> bool {
         let config = config::get().expect("Failed to load config");
 
+        let mut ignore_space_check = false;
+        let mut temp_dir = None;
+        let mut wait = false;
+
+        for arg in &values {
+            match arg.name.as_str() {
+                "--ignore-space-check" => ignore_space_check = true,
+                "--temp-dir" => temp_dir = Some(arg.value.clone()),
+                "--wait" => wait = true,
+                _ => unreachable!()
+            }
+        }
+
+        let staging_folder = match temp::staging_folder(temp_dir.as_deref()) {
+            Ok(folder) => folder.to_string_lossy().to_string(),
+            Err(err) => {
+                error(format!("Failed to create staging folder: {}", err));
+
+                return false;
+            }
+        };
+
         let game_path = {
             if config.paths.game.is_empty() {
                 error("Game path is not specified");
@@ -45,9 +75,18 @@ impl Command for VoiceDownload {
             config.paths.game
         };
 
+        let _lock = match Lock::acquire(&game_path, wait) {
+            Ok(lock) => lock,
+            Err(err) => {
+                error(err.to_string());
+
+                return false;
+            }
+        };
+
         let mut packages = HashSet::new();
 
-        for arg in &args[1..] {
+        for arg in &positional_args(&args[1..], &["--temp-dir"]) {
             match VoiceLocale::from_str(arg) {
                 Some(locale) => match VoicePackage::with_locale(locale) {
                     Ok(package) => {
@@ -66,69 +105,90 @@ impl Command for VoiceDownload {
             }
         }
 
-        let progress = Arc::new(Mutex::new(linya::Progress::new()));
-        let mut handlers = Vec::new();
+        let mut diffs = Vec::new();
 
         for package in packages {
             match package.try_get_diff() {
-                Ok(diff) => {
-                    let thread_progress = progress.clone();
-                    let thread_game_path = game_path.clone();
-
-                    handlers.push(std::thread::spawn(move || {
-                        let (download_size, unpacked_size) = diff.size().unwrap();
-                        
-                        let downloading_bar = Arc::new(thread_progress.lock().unwrap().bar(
-                            download_size as usize,
-                            format!("{} ({} GB)", package.locale().to_name(), format_size(download_size))
-                        ));
-
-                        let unpacking_bar = Arc::new(thread_progress.lock().unwrap().bar(
-                            unpacked_size as usize,
-                            format!("{} ({} GB)", package.locale().to_name(), format_size(unpacked_size))
-                        ));
-
-                        let result = diff.install_to(thread_game_path, move |state| {
-                            let mut thread_progress = thread_progress.lock().unwrap();
-
-                            match state {
-                                InstallerUpdate::CheckingFreeSpace(_) => (),
-                                InstallerUpdate::DownloadingStarted(_) => (),
-                                InstallerUpdate::DownloadingProgress(curr, _) => {
-                                    thread_progress.set_and_draw(&downloading_bar, curr as usize);
-                                },
-                                InstallerUpdate::DownloadingFinished => {
-                                    thread_progress.set_and_draw(&downloading_bar, download_size as usize);
-                                },
-                                InstallerUpdate::DownloadingError(_) => {
-                                    // error("Failed to download package"); // todo
-                                },
-                                InstallerUpdate::UnpackingStarted(_) => (),
-                                InstallerUpdate::UnpackingProgress(curr, _) => {
-                                    thread_progress.set_and_draw(&unpacking_bar, curr as usize);
-                                },
-                                InstallerUpdate::UnpackingFinished => {
-                                    thread_progress.set_and_draw(&unpacking_bar, unpacked_size as usize);
-                                },
-                                InstallerUpdate::UnpackingError(_) => {
-                                    // error("Failed to unpack package"); // todo
-                                }
-                            }
-                        });
-
-                        if result.is_err() {
-                            // todo
-                        }
-                    }));
-                },
+                Ok(diff) => diffs.push((package, diff)),
                 Err(err) => error(format!("Failed to find difference for {} package: {}", package.locale().to_name(), err))
             }
         }
 
+        // Archives are downloaded to the temp folder and then unpacked to the game folder
+        let mut requirements = Vec::new();
+
+        for (_, diff) in &diffs {
+            if let Some((download_size, unpacked_size)) = diff.size() {
+                requirements.push((PathBuf::from(&staging_folder), download_size));
+                requirements.push((PathBuf::from(&game_path), unpacked_size));
+            }
+        }
+
+        if !space::preflight(&requirements, ignore_space_check) {
+            return false;
+        }
+
+        let progress = Arc::new(Mutex::new(linya::Progress::new()));
+        let mut handlers = Vec::new();
+
+        for (package, diff) in diffs {
+            let thread_progress = progress.clone();
+            let thread_game_path = game_path.clone();
+            let thread_staging_folder = staging_folder.clone();
+
+            handlers.push(std::thread::spawn(move || {
+                let (download_size, unpacked_size) = diff.size().unwrap();
+                
+                let downloading_bar = Arc::new(thread_progress.lock().unwrap().bar(
+                    download_size as usize,
+                    format!("{} ({} GB)", package.locale().to_name(), format_size(download_size))
+                ));
+
+                let unpacking_bar = Arc::new(thread_progress.lock().unwrap().bar(
+                    unpacked_size as usize,
+                    format!("{} ({} GB)", package.locale().to_name(), format_size(unpacked_size))
+                ));
+
+                let result = diff.install_to_by(thread_game_path, Some(thread_staging_folder), move |state| {
+                    let mut thread_progress = thread_progress.lock().unwrap();
+
+                    match state {
+                        InstallerUpdate::CheckingFreeSpace(_) => (),
+                        InstallerUpdate::DownloadingStarted(_) => (),
+                        InstallerUpdate::DownloadingProgress(curr, _) => {
+                            thread_progress.set_and_draw(&downloading_bar, curr as usize);
+                        },
+                        InstallerUpdate::DownloadingFinished => {
+                            thread_progress.set_and_draw(&downloading_bar, download_size as usize);
+                        },
+                        InstallerUpdate::DownloadingError(_) => {
+                            // error("Failed to download package"); // todo
+                        },
+                        InstallerUpdate::UnpackingStarted(_) => (),
+                        InstallerUpdate::UnpackingProgress(curr, _) => {
+                            thread_progress.set_and_draw(&unpacking_bar, curr as usize);
+                        },
+                        InstallerUpdate::UnpackingFinished => {
+                            thread_progress.set_and_draw(&unpacking_bar, unpacked_size as usize);
+                        },
+                        InstallerUpdate::UnpackingError(_) => {
+                            // error("Failed to unpack package"); // todo
+                        }
+                    }
+                });
+
+                if result.is_err() {
+                    // todo
+                }
+            }));
+        }
+
         for handler in handlers {
             handler.join().unwrap();
         }
 
+        temp::remove_staging_folder(temp_dir.as_deref());
+
         true
     }
 }
@@ -3,10 +3,12 @@ use commandor::prelude::*;
 use anime_game_core::prelude::*;
 use anime_game_core::genshin::prelude::*;
 
-use anime_game_core::genshin::repairer::try_get_voice_integrity_files;
-
 use crate::lib::output::*;
 use crate::lib::config;
+use crate::lib::integrity;
+use crate::lib::positional_args;
+use crate::lib::manifest::ManifestFile;
+use crate::lib::health::VerificationScope;
 use crate::lib::command_traits::repair::*;
 
 pub struct VoiceRepair {
@@ -14,38 +16,59 @@ pub struct VoiceRepair {
 }
 
 impl RepairFiles for VoiceRepair {
-    fn try_get_integrity_files(args: Vec<String>) -> anyhow::Result<Vec<IntegrityFile>> {
+    fn try_get_integrity_files(args: Vec<String>, repairing_config: &RepairFilesConfig, manifest: Option<&[ManifestFile]>) -> anyhow::Result<(Vec<IntegrityFile>, VerificationScope)> {
         let config = config::get().expect("Failed to get config");
 
         let mut files = Vec::new();
         let mut locales = Vec::new();
-        
-        for arg in &args[1..] {
-            if let Some(locale) = VoiceLocale::from_str(arg) {
-                match VoicePackage::with_locale(locale) {
-                    Ok(package) => {
-                        if !package.is_installed_in(&config.paths.game) {
-                            warn(format!("{} package is not installed", locale.to_name()))
-                        }
-
-                        else {
-                            locales.push(locale);
-
-                            files.append(&mut try_get_voice_integrity_files(locale, None)?);
-                        }
-                    },
-                    Err(err) => warn(format!("Failed to get {} package: {}", locale.to_name(), err))
-                }
+
+        let installed = integrity::installed_locales(&config.paths.game)?;
+
+        for arg in &args {
+            match VoiceLocale::from_str(arg) {
+                Some(locale) => {
+                    if !installed.contains(&locale) {
+                        warn(format!("{} package is not installed", locale.to_name()))
+                    }
+
+                    else if !locales.contains(&locale) {
+                        locales.push(locale);
+                    }
+                },
+                None => anyhow::bail!("Unknown voice package: {}", arg)
             }
         }
 
+        // Verify all the installed packages by default
+        if args.is_empty() {
+            if installed.is_empty() {
+                warn("No voice packages installed");
+            }
+
+            locales = installed;
+        }
+
+        let mut scope = VerificationScope::default();
+
+        if repairing_config.all {
+            files.append(&mut integrity::game_files(manifest)?);
+
+            scope.game = true;
+        }
+
+        for locale in &locales {
+            files.append(&mut integrity::voice_files(*locale, manifest)?);
+
+            scope.voices.push(locale.to_name().to_string());
+        }
+
         let locales = locales.into_iter().fold(String::new(), |acc, s| acc + s.to_name() + ", ");
 
         if !locales.is_empty() {
             notice(format!("Verifying locales: {}", &locales[..locales.len() - 2]));
         }
 
-        Ok(files)
+        Ok((files, scope))
     }
 }
 
@@ -67,6 +90,8 @@ impl Command for VoiceRepair {
     }
 
     fn execute(&self, args: Vec<String>, values: Vec<ArgumentValue>) -> bool {
+        let args = positional_args(&args[1..], &Self::get_value_args());
+
         Self::repair(RepairFilesConfig::from_args(values), args)
     }
 }
@@ -1,31 +1,85 @@
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use std::cmp::min;
+use std::path::{Path, PathBuf};
 
 use commandor::prelude::*;
 
 use anime_game_core::repairer::IntegrityFile;
 
+use crate::lib::manifest::{self, ManifestFile};
+
 use crate::lib::config;
+use crate::lib::quarantine;
+use crate::lib::source;
+use crate::lib::storage;
+use crate::lib::space;
+use crate::lib::temp;
+use crate::lib::lock::Lock;
+use crate::lib::hash::{self, ReadLimits};
+use crate::lib::throttle::Semaphore;
+use crate::lib::health::{VerificationState, VerificationScope};
 use crate::lib::output::*;
 use crate::lib::format_size;
 
 #[derive(Debug, Clone)]
 pub struct RepairFilesConfig {
     pub verify_threads: usize,
+
+    /// Choose verify threads number based on the game's drive type
+    pub auto_threads: bool,
+
     pub repair_threads: usize,
     pub ignore: Vec<String>,
     pub just_verify: bool,
-    pub fast: bool
+
+    /// Verify both game and voice packages files
+    pub all: bool,
+
+    pub fast: bool,
+    pub orphans: bool,
+    pub clean_orphans: bool,
+    pub quarantine: Option<String>,
+    pub source: Option<String>,
+    pub manifest: Option<String>,
+
+    /// Memory limit for verification buffers, in bytes
+    /// 
+    /// Can't be lower than the size of one large file buffer
+    pub max_memory: Option<u64>,
+
+    /// Number of additional attempts to repair the file if it still doesn't match
+    pub retries: usize,
+
+    pub ignore_space_check: bool,
+
+    /// Folder for temporary data. Config value or system temp folder is used if not specified
+    pub temp_dir: Option<String>,
+
+    /// Wait until the game is unlocked by other processes
+    pub wait: bool
 }
 
 impl std::default::Default for RepairFilesConfig {
     fn default() -> Self {
         Self {
             verify_threads: 4,
+            auto_threads: false,
             repair_threads: 4,
             ignore: vec![],
             just_verify: false,
-            fast: false
+            all: false,
+            fast: false,
+            orphans: false,
+            clean_orphans: false,
+            quarantine: None,
+            source: None,
+            manifest: None,
+            max_memory: None,
+            retries: 2,
+            ignore_space_check: false,
+            temp_dir: None,
+            wait: false
         }
     }
 }
@@ -36,15 +90,42 @@ impl RepairFilesConfig {
 
         for arg in &args {
             match arg.name.as_str() {
+                "--threads" | "--verify-threads" if arg.value == "auto" => config.auto_threads = true,
                 "--threads" => {
                     config.verify_threads = arg.value.parse::<usize>().expect("Wrong threads num");
                     config.repair_threads = config.verify_threads;
                 },
-                "--verify-threads" => config.verify_threads = arg.value.parse::<usize>().expect("Wrong threads num"),
+                "--verify-threads" => {
+                    config.verify_threads = arg.value.parse::<usize>().expect("Wrong threads num");
+
+                    // Explicit verify threads num wins over "--threads auto"
+                    config.auto_threads = false;
+                },
                 "--repair-threads" => config.repair_threads = arg.value.parse::<usize>().expect("Wrong threads num"),
                 "--ignore" => config.ignore = arg.value.split(',').map(|f| f.to_string()).collect(),
                 "--verify" => config.just_verify = true,
+                "--all" => config.all = true,
                 "--fast" => config.fast = true,
+                "--orphans" => config.orphans = true,
+                "--clean-orphans" => config.clean_orphans = true,
+                "--quarantine" => config.quarantine = Some(arg.value.clone()),
+                "--source" => config.source = Some(arg.value.clone()),
+                "--manifest" => config.manifest = Some(arg.value.clone()),
+                "--retries" => config.retries = arg.value.parse::<usize>().expect("Wrong retries num"),
+                "--ignore-space-check" => config.ignore_space_check = true,
+                "--temp-dir" => config.temp_dir = Some(arg.value.clone()),
+                "--wait" => config.wait = true,
+                "--max-memory" => {
+                    let max_memory = arg.value.parse::<u64>().ok()
+                        .and_then(|megabytes| megabytes.checked_mul(1024 * 1024))
+                        .expect("Wrong memory limit");
+
+                    if max_memory < hash::LARGE_BUFFER_SIZE as u64 {
+                        panic!("Memory limit can't be lower than {} MB", hash::LARGE_BUFFER_SIZE / 1024 / 1024);
+                    }
+
+                    config.max_memory = Some(max_memory);
+                },
                 _ => unreachable!()
             }
         }
@@ -53,6 +134,89 @@ impl RepairFilesConfig {
     }
 }
 
+/// Verify the file reading it with streamed buffers
+/// 
+/// Fast mode compares file sizes only
+fn verify_file(file: &IntegrityFile, game_path: &str, fast: bool, limits: &ReadLimits) -> bool {
+    let path = Path::new(game_path).join(&file.path);
+
+    match std::fs::metadata(&path) {
+        Ok(metadata) if metadata.len() == file.size => {
+            fast || match hash::md5_limited(&path, limits) {
+                Ok(md5) => md5.eq_ignore_ascii_case(&file.md5),
+                Err(_) => false
+            }
+        },
+        _ => false
+    }
+}
+
+/// Result of the file repairing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepairStatus {
+    /// File was repaired and its hash matches the integrity file
+    Verified {
+        from_source: bool
+    },
+
+    /// File was downloaded but its hash still doesn't match the integrity file
+    Mismatching,
+
+    /// Failed to download the file
+    Failed(String)
+}
+
+/// Repair the file and verify it again, retrying with increasing delays if it still doesn't match
+fn repair_file(file: &IntegrityFile, game_path: &str, source: Option<(&str, &Path)>, retries: usize, limits: &ReadLimits) -> RepairStatus {
+    let mut status = RepairStatus::Mismatching;
+
+    for attempt in 0..=retries {
+        if attempt > 0 {
+            std::thread::sleep(Duration::from_secs(1 << min(attempt - 1, 5)));
+        }
+
+        // Try to take the file from the reference installation first.
+        // It's already verified by the source so there's no need to do it again
+        if let (Some((source, staging_folder)), 0) = (source, attempt) {
+            if let Ok(true) = source::try_repair(source, file, game_path, staging_folder) {
+                return RepairStatus::Verified {
+                    from_source: true
+                };
+            }
+        }
+
+        status = match file.repair(game_path) {
+            Ok(_) => {
+                if verify_file(file, game_path, false, limits) {
+                    return RepairStatus::Verified {
+                        from_source: false
+                    };
+                }
+
+                RepairStatus::Mismatching
+            },
+            Err(err) => RepairStatus::Failed(format!("{:?}", err))
+        };
+    }
+
+    status
+}
+
+/// Remember result of the verification so it can be shown by the info command
+/// 
+/// Result of the previous verification is kept if it covered more files
+fn save_state<'a>(fast: bool, verified: &[PathBuf], broken: impl Iterator<Item = &'a IntegrityFile>, scope: &VerificationScope) {
+    let mut state = VerificationState::new(fast, verified.len(), broken.map(|file| file.path.clone()).collect(), scope.clone());
+
+    if let Ok(Some(previous)) = VerificationState::read() {
+        state = state.merge(previous, verified);
+    }
+
+    if let Err(err) = state.write() {
+        warn(format!("Failed to save verification state: {}", err));
+    }
+}
+
 fn calc_size(files: &Vec<IntegrityFile>) -> u64 {
     let mut size = 0;
 
@@ -63,19 +227,119 @@ fn calc_size(files: &Vec<IntegrityFile>) -> u64 {
     size
 }
 
+/// Process items in the given number of threads, drawing progress bar for each of them
+/// 
+/// Items are split between threads so each of them gets roughly the same amount of bytes to process.
+/// Returns all the values returned by the worker
+pub fn run_threads<T, R, F>(items: Vec<T>, threads: usize, size: fn(&T) -> u64, worker: F) -> Vec<R>
+where
+    T: Send + 'static,
+    R: Send + 'static,
+    F: Fn(T) -> Option<R> + Send + Sync + 'static
+{
+    let threads = min(threads, items.len()).max(1);
+
+    let (result_send, result_recv) = std::sync::mpsc::channel();
+    let mut handlers = Vec::new();
+
+    let progress = Arc::new(Mutex::new(linya::Progress::new()));
+    let worker = Arc::new(worker);
+
+    // Prepare threads
+    let average_thread_size = items.iter().map(size).sum::<u64>() / threads as u64;
+
+    let mut items = items.into_iter().peekable();
+    let mut j = 0;
+
+    for i in 0..threads {
+        let mut items_part = Vec::new();
+        let mut items_part_size = 0;
+
+        // Last thread takes everything that left
+        while items_part_size < average_thread_size || i == threads - 1 {
+            match items.next() {
+                Some(item) => {
+                    items_part_size += size(&item);
+                    items_part.push(item);
+                },
+                None => break
+            }
+        }
+
+        if !items_part.is_empty() {
+            let thread_worker = worker.clone();
+            let thread_progress = progress.clone();
+            let thread_result_send = result_send.clone();
+
+            j += 1;
+
+            let bar = thread_progress.lock().unwrap().bar(items_part.len(), format!("Thread {} ({} GB of {} files)", j, format_size(items_part_size), items_part.len()));
+
+            // Run thread
+            #[allow(unused_must_use)]
+            handlers.push(std::thread::spawn(move || {
+                for item in items_part {
+                    if let Some(result) = thread_worker(item) {
+                        thread_result_send.send(result);
+                    }
+
+                    thread_progress.lock().unwrap().inc_and_draw(&bar, 1);
+                }
+            }));
+        }
+    }
+
+    // Sync threads
+    for handler in handlers {
+        handler.join().unwrap();
+    }
+
+    drop(result_send);
+
+    result_recv.into_iter().collect()
+}
+
 pub trait RepairFiles {
     fn get_command_args() -> Vec<Box<dyn Argument>> {
         vec![
-            Default::new("--threads", vec!["-t"], true), // Sets both --verify-threads and --repair-threads
+            Default::new("--threads", vec!["-t"], true), // Sets both --verify-threads and --repair-threads. "auto" chooses verify threads by the drive type
             Default::new("--verify-threads", vec!["-vt"], true),
             Default::new("--repair-threads", vec!["-rt"], true),
             Setter::new("--ignore", vec!["-i", "--skip"], "=", true), // Case insensitive
             Flag::new("--verify", vec!["-v"]), // Verify only; don't repair
-            Flag::new("--fast", vec!["-f"]) // Fast mode; compares files' sizes only
+            Flag::new("--all", vec!["-a"]), // Verify game and voice packages files together
+            Flag::new("--fast", vec!["-f"]), // Fast mode; compares files' sizes only
+            Default::new("--quarantine", vec![], true), // Move broken files to the folder before repairing
+            Default::new("--source", vec!["-s"], true), // Local game folder or LAN server to take files from before downloading them
+            Default::new("--manifest", vec!["-m"], true), // Verify files using saved manifest instead of fetching integrity files
+            Default::new("--max-memory", vec![], true), // Memory limit in MB for verification buffers
+            Default::new("--retries", vec!["-r"], true), // Number of additional attempts to repair files which still don't match
+            Flag::with_name("--ignore-space-check"), // Don't abort repairing if there's not enough free space
+            Default::new("--temp-dir", vec![], true), // Folder for files taken from --source
+            Flag::with_name("--wait") // Wait until other commands modifying the game are finished
+        ]
+    }
+
+    /// Get names and aliases of the command arguments which take a value
+    fn get_value_args() -> Vec<&'static str> {
+        vec![
+            "--threads", "-t",
+            "--verify-threads", "-vt",
+            "--repair-threads", "-rt",
+            "--quarantine",
+            "--source", "-s",
+            "--manifest", "-m",
+            "--max-memory",
+            "--retries", "-r",
+            "--temp-dir"
         ]
     }
 
-    fn try_get_integrity_files(args: Vec<String>) -> anyhow::Result<Vec<IntegrityFile>>;
+    /// Get integrity files to verify and describe which files they are
+    /// 
+    /// `args` are positional arguments of the command. If the manifest is specified,
+    /// files should be taken from it instead of fetching them
+    fn try_get_integrity_files(args: Vec<String>, repairing_config: &RepairFilesConfig, manifest: Option<&[ManifestFile]>) -> anyhow::Result<(Vec<IntegrityFile>, VerificationScope)>;
 
     fn repair(mut repairing_config: RepairFilesConfig, args: Vec<String>) -> bool {
         let config = config::get().expect("Failed to load config");
@@ -91,10 +355,44 @@ pub trait RepairFiles {
             config.paths.game
         };
 
-        notice("Fetching integrity files...");
+        // Verification doesn't modify anything so it can be done in parallel with other commands
+        let _lock = if !repairing_config.just_verify {
+            match Lock::acquire(&game_path, repairing_config.wait) {
+                Ok(lock) => Some(lock),
+                Err(err) => {
+                    error(err.to_string());
+
+                    return false;
+                }
+            }
+        } else {
+            None
+        };
+
+        let manifest = match &repairing_config.manifest {
+            Some(path) => {
+                notice(format!("Reading integrity files from {}...", path));
+
+                match manifest::read(path) {
+                    Ok(files) => Some(files),
+                    Err(err) => {
+                        error(format!("Failed to read manifest: {}", err));
+
+                        return false;
+                    }
+                }
+            },
+            None => {
+                notice("Fetching integrity files...");
+
+                None
+            }
+        };
+
+        match Self::try_get_integrity_files(args, &repairing_config, manifest.as_deref()) {
+            Ok((mut files, mut scope)) => {
+                scope.ignore = repairing_config.ignore.clone();
 
-        match Self::try_get_integrity_files(args) {
-            Ok(mut files) => {
                 // Skip ignored files
                 files = files.into_iter().filter(|file| {
                     for line in &repairing_config.ignore {
@@ -114,74 +412,46 @@ pub trait RepairFiles {
                     return false;
                 }
 
-                // Don't try to run 4 threads for 1 file
-                repairing_config.verify_threads = min(repairing_config.verify_threads, files.len());
-
-                notice(format!("Verifying {} files ({} GB) in {} threads:\n", files.len(), format_size(calc_size(&files)), repairing_config.verify_threads));
+                if repairing_config.auto_threads {
+                    let (threads, reason) = storage::suggest_verify_threads(&game_path);
 
-                let (broken_send, broken_recv) = std::sync::mpsc::channel();
-                let mut handlers = Vec::new();
+                    notice(format!("Using {} verify threads: {}", threads, reason));
 
-                let progress = Arc::new(Mutex::new(linya::Progress::new()));
+                    repairing_config.verify_threads = threads;
+                }
 
-                // Prepare threads
-                let average_thread_size = calc_size(&files) / repairing_config.verify_threads as u64;
+                // Don't try to run 4 threads for 1 file
+                repairing_config.verify_threads = min(repairing_config.verify_threads, files.len());
 
-                let mut i = 0;
-                let mut j = 0;
+                notice(format!("Verifying {} files ({} GB) in {} threads:\n", files.len(), format_size(calc_size(&files)), repairing_config.verify_threads));
 
-                for _ in 0..repairing_config.verify_threads {
-                    let mut files_part = Vec::new();
-                    let mut files_part_size = 0;
+                let limits = Arc::new(ReadLimits {
+                    memory: repairing_config.max_memory.map(Semaphore::new),
 
-                    while files_part_size < average_thread_size && i < files.len() {
-                        files_part.push(files[i].clone());
-                        files_part_size += files[i].size;
+                    // Parallel reading of large files makes HDDs seek all the time
+                    large_reads: match storage::is_rotational(&game_path) {
+                        Some(true) if !repairing_config.fast => {
+                            notice("Game is located on a rotational drive so large files will be read one at a time\n");
 
-                        i += 1;
+                            Some(Semaphore::new(1))
+                        },
+                        _ => None
                     }
+                });
 
-                    if !files_part.is_empty() {
-                        let game_path_ref = game_path.clone();
-
-                        let thread_progress = progress.clone();
-                        let thread_broken_send = broken_send.clone();
-
-                        j += 1;
-
-                        let bar = thread_progress.lock().unwrap().bar(files_part.len(), format!("Thread {} ({} GB of {} files)", j, format_size(files_part_size), files_part.len()));
-
-                        // Run thread
-                        #[allow(unused_must_use)]
-                        handlers.push(std::thread::spawn(move || {
-                            for file in files_part {
-                                let status = if repairing_config.fast {
-                                    file.fast_verify(&game_path_ref)
-                                } else {
-                                    file.verify(&game_path_ref)
-                                };
+                let game_path_ref = game_path.clone();
+                let thread_limits = limits.clone();
+                let fast = repairing_config.fast;
 
-                                if !status {
-                                    thread_broken_send.send(file);
-                                }
+                let verified_paths = files.iter().map(|file| file.path.clone()).collect::<Vec<_>>();
 
-                                thread_progress.lock().unwrap().inc_and_draw(&bar, 1);
-                            }
-                        }));
+                let broken_files = run_threads(files, repairing_config.verify_threads, |file| file.size, move |file: IntegrityFile| {
+                    if verify_file(&file, &game_path_ref, fast, &thread_limits) {
+                        None
+                    } else {
+                        Some(file)
                     }
-                }
-
-                // Sync threads
-                for handler in handlers {
-                    handler.join().unwrap();
-                }
-
-                // Fetch broken files
-                let mut broken_files = Vec::new();
-
-                while let Ok(file) = broken_recv.try_recv() {
-                    broken_files.push(file);
-                }
+                });
 
                 // Repair broken files
 
@@ -196,57 +466,124 @@ pub trait RepairFiles {
                     output
                 });
 
+                save_state(repairing_config.fast, &verified_paths, broken_files.iter(), &scope);
+
                 if !repairing_config.just_verify && !broken_files.is_empty() {
+                    // Repaired files are written to the game folder, and broken ones are moved to the quarantine
+                    let mut requirements = vec![(PathBuf::from(&game_path), calc_size(&broken_files))];
+
+                    if let Some(folder) = &repairing_config.quarantine {
+                        requirements.push((PathBuf::from(folder), calc_size(&broken_files)));
+                    }
+
+                    if !space::preflight(&requirements, repairing_config.ignore_space_check) {
+                        return false;
+                    }
+
+                    // Keep broken files for further investigation
+                    if let Some(folder) = &repairing_config.quarantine {
+                        match quarantine::quarantine(folder, &game_path, &broken_files) {
+                            Ok(session) => {
+                                println!();
+                                notice(format!("Broken files moved to quarantine session {:?}", session));
+                            },
+                            Err(err) => {
+                                error(format!("Failed to quarantine broken files: {}", err));
+
+                                return false;
+                            }
+                        }
+                    }
+
                     // Don't try to run 4 threads for 1 file
                     repairing_config.repair_threads = min(repairing_config.repair_threads, broken_files.len());
                     
                     println!();
                     notice(format!("Repairing {} files in {} threads:\n", broken_files.len(), repairing_config.repair_threads));
 
-                    let (failed_send, failed_recv) = std::sync::mpsc::channel();
-                    let mut handlers = Vec::new();
-
-                    let progress = Arc::new(Mutex::new(linya::Progress::new()));
+                    if let Some(source) = &repairing_config.source {
+                        notice(format!("Files will be taken from {} when possible\n", source));
+                    }
 
-                    // Prepare threads
-                    let step = files.len() / repairing_config.repair_threads;
+                    // Generated manifests don't know where to download files from
+                    if repairing_config.source.is_none() && broken_files.iter().any(|file| file.base_url.is_empty()) {
+                        warn("Manifest doesn't contain download urls of some files so they can only be taken from --source\n");
+                    }
 
-                    for i in 0..repairing_config.repair_threads {
-                        let files_part = Vec::from(if i == repairing_config.repair_threads - 1 {
-                            &broken_files[i * step..]
-                        } else {
-                            &broken_files[i * step..(i + 1) * step]
-                        });
+                    let staging_folder = match temp::staging_folder(repairing_config.temp_dir.as_deref()) {
+                        Ok(folder) => folder,
+                        Err(err) => {
+                            error(format!("Failed to create staging folder: {}", err));
 
-                        let game_path_ref = game_path.clone();
+                            return false;
+                        }
+                    };
+
+                    let game_path_ref = game_path.clone();
+                    let source_ref = repairing_config.source.clone();
+                    let staging_folder_ref = staging_folder.clone();
+                    let retries = repairing_config.retries;
+                    let thread_limits = limits.clone();
+
+                    let results = run_threads(broken_files.clone(), repairing_config.repair_threads, |file| file.size, move |file: IntegrityFile| {
+                        let source = source_ref.as_deref().map(|source| (source, staging_folder_ref.as_path()));
+                        let status = repair_file(&file, &game_path_ref, source, retries, &thread_limits);
+
+                        Some((file, status))
+                    });
+
+                    temp::remove_staging_folder(repairing_config.temp_dir.as_deref());
+
+                    let mut verified = 0;
+                    let mut from_source = 0;
+                    let mut mismatching = Vec::new();
+                    let mut failed = Vec::new();
+
+                    for (file, status) in results {
+                        match status {
+                            RepairStatus::Verified { from_source: true } => {
+                                verified += 1;
+                                from_source += 1;
+                            },
+                            RepairStatus::Verified { from_source: false } => verified += 1,
+                            RepairStatus::Mismatching => mismatching.push(file),
+                            RepairStatus::Failed(err) => failed.push((file, err))
+                        }
+                    }
 
-                        let thread_progress = progress.clone();
-                        let thread_failed_send = failed_send.clone();
+                    println!();
 
-                        let bar = thread_progress.lock().unwrap().bar(files_part.len(), format!("Thread {} ({} GB of {} files)", i + 1, format_size(calc_size(&files_part)), files_part.len()));
+                    notice(if repairing_config.source.is_some() {
+                        format!("Repaired and verified {} of {} files ({} taken from the source)", verified, broken_files.len(), from_source)
+                    } else {
+                        format!("Repaired and verified {} of {} files", verified, broken_files.len())
+                    });
 
-                        // Run thread
-                        #[allow(unused_must_use)]
-                        handlers.push(std::thread::spawn(move || {
-                            for file in files_part {
-                                if let Err(err) = file.repair(game_path_ref.clone()) {
-                                    thread_failed_send.send((file, err));
-                                }
+                    if !mismatching.is_empty() {
+                        warn({
+                            let mut output = vec![format!("Repaired but still mismatching {} files", mismatching.len())];
 
-                                thread_progress.lock().unwrap().inc_and_draw(&bar, 1);
+                            for file in &mismatching {
+                                output.push(format!("- {:?}", file.path));
                             }
-                        }));
-                    }
 
-                    // Sync threads
-                    for handler in handlers {
-                        handler.join().unwrap();
+                            output
+                        });
                     }
 
-                    // Print failed to repair files
-                    while let Ok((file, err)) = failed_recv.try_recv() {
-                        error(format!("Failed to repair {:?}: {:?}", file.path, err));
+                    if !failed.is_empty() {
+                        error({
+                            let mut output = vec![format!("Failed to download {} files", failed.len())];
+
+                            for (file, err) in &failed {
+                                output.push(format!("- {:?}: {}", file.path, err));
+                            }
+
+                            output
+                        });
                     }
+
+                    save_state(repairing_config.fast, &verified_paths, mismatching.iter().chain(failed.iter().map(|(file, _)| file)), &scope);
                 }
             },
             Err(err) => error(format!("Failed to get integrity files: {}", err))
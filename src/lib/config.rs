@@ -30,16 +30,19 @@ pub fn get() -> Result<Config, Error> {
 }
 
 pub fn update(config: Config) -> Result<(), Error> {
-    let mut file = File::create(CONFIG_FILE)?;
+    let toml = match toml::to_string(&config) {
+        Ok(toml) => toml,
+        Err(err) => return Err(Error::new(ErrorKind::InvalidData, format!("Failed to encode data into toml format: {err}")))
+    };
 
-    match toml::to_string(&config) {
-        Ok(toml) => {
-            file.write_all(toml.as_bytes())?;
+    // Write to the temp file first so the config is never left partially written
+    let temp = format!("{CONFIG_FILE}.tmp");
+    let mut file = File::create(&temp)?;
 
-            Ok(())
-        },
-        Err(err) => Err(Error::new(ErrorKind::InvalidData, format!("Failed to encode data into toml format: {err}")))
-    }
+    file.write_all(toml.as_bytes())?;
+    file.sync_all()?;
+
+    std::fs::rename(temp, CONFIG_FILE)
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -52,12 +55,64 @@ pub struct Config {
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct Paths {
     pub game: String,
-    pub patch: String
+    pub patch: String,
+
+    /// Folder for downloaded archives and other temporary data. System temp folder is used if empty
+    #[serde(default)]
+    pub temp: String
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Patch {
-    pub hosts: Vec<String>
+    pub hosts: Vec<String>,
+
+    /// Block telemetry servers only for the game by running it with its own hosts file
+    /// in a user namespace instead of modifying the system one
+    #[serde(default)]
+    pub isolated_hosts: bool,
+
+    /// Time in seconds patch hosts check results are used by `patch sync --fastest`. 0 disables caching
+    #[serde(default = "default_mirrors_cache_ttl")]
+    pub mirrors_cache_ttl: u64,
+
+    /// Commit or tag the local patch repository is pinned to. Latest revision is used if empty
+    #[serde(default)]
+    pub revision: String,
+
+    /// Sync and apply the patch after game updates and before running the game
+    #[serde(default)]
+    pub auto_apply: bool,
+
+    /// Allow automatic applying of the patch in testing stage
+    #[serde(default)]
+    pub allow_testing: bool,
+
+    /// Refuse to apply the patch if its revision is not signed by one of the trusted keys
+    #[serde(default)]
+    pub require_signature: bool,
+
+    /// Fingerprints of the gpg keys patch revisions can be signed with
+    #[serde(default)]
+    pub trusted_keys: Vec<String>
+}
+
+fn default_mirrors_cache_ttl() -> u64 {
+    3600
+}
+
+impl Default for Patch {
+    fn default() -> Self {
+        Self {
+            hosts: Vec::new(),
+            isolated_hosts: false,
+            mirrors_cache_ttl: default_mirrors_cache_ttl(),
+            revision: String::new(),
+            auto_apply: false,
+            allow_testing: false,
+            require_signature: false,
+            trusted_keys: Vec::new()
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -0,0 +1,64 @@
+use std::path::Path;
+use std::fs::File;
+use std::io::Read;
+
+use md5::{Md5, Digest};
+
+use crate::lib::throttle::Semaphore;
+
+/// Files bigger than this size are considered large
+pub const LARGE_FILE_SIZE: u64 = 32 * 1024 * 1024;
+
+/// Buffer size used to hash small files
+pub const SMALL_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Buffer size used to hash large files. Bigger reads mean less seeking on HDDs
+pub const LARGE_BUFFER_SIZE: usize = 8 * 1024 * 1024;
+
+/// Limits shared between threads reading large files
+#[derive(Debug, Default)]
+pub struct ReadLimits {
+    /// Amount of memory in bytes which buffers of concurrent large reads can use
+    pub memory: Option<Semaphore>,
+
+    /// Number of concurrent large reads
+    pub large_reads: Option<Semaphore>
+}
+
+/// Calculate md5 hash of the file
+pub fn md5<T: AsRef<Path>>(path: T) -> std::io::Result<String> {
+    md5_limited(path, &ReadLimits::default())
+}
+
+/// Calculate md5 hash of the file, reading it with a fixed-size buffer
+/// 
+/// Large files wait until the limits allow to read them
+pub fn md5_limited<T: AsRef<Path>>(path: T, limits: &ReadLimits) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+
+    let large = file.metadata()?.len() >= LARGE_FILE_SIZE;
+
+    let _reads_guard = match &limits.large_reads {
+        Some(large_reads) if large => Some(large_reads.acquire(1)),
+        _ => None
+    };
+
+    let _memory_guard = match &limits.memory {
+        Some(memory) if large => Some(memory.acquire(LARGE_BUFFER_SIZE as u64)),
+        _ => None
+    };
+
+    let mut buffer = vec![0; if large { LARGE_BUFFER_SIZE } else { SMALL_BUFFER_SIZE }];
+    let mut hasher = Md5::new();
+
+    loop {
+        match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => hasher.update(&buffer[..read]),
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => (),
+            Err(err) => return Err(err)
+        }
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
@@ -0,0 +1,221 @@
+use std::path::{Path, PathBuf};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{Read, Write};
+
+use serde::{Serialize, Deserialize};
+
+/// File with the result of the last files verification
+pub const STATE_FILE: &str = "verification.toml";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerificationState {
+    /// Time of verification in RFC 3339 format
+    pub verified: String,
+
+    /// Whether only files sizes were compared
+    pub fast: bool,
+
+    /// Number of verified files
+    pub files: usize,
+
+    /// Files which are still broken after verification (and repairing if it was performed)
+    pub broken: Vec<PathBuf>,
+
+    /// Which files were verified
+    #[serde(default)]
+    pub scope: VerificationScope
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VerificationScope {
+    /// Whether the game files were verified
+    pub game: bool,
+
+    /// Names of the verified voice packages
+    pub voices: Vec<String>,
+
+    /// Ignored paths patterns
+    pub ignore: Vec<String>
+}
+
+impl VerificationScope {
+    /// Check if this scope has all the files of the other one
+    pub fn covers(&self, other: &Self) -> bool {
+        (self.game || !other.game)
+            && other.voices.iter().all(|voice| self.voices.contains(voice))
+            && self.ignore.iter().all(|pattern| other.ignore.contains(pattern))
+    }
+
+    /// Describe verified files, e.g. `game, English voice package`
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+
+        if self.game {
+            parts.push(String::from("game"));
+        }
+
+        match self.voices.len() {
+            0 => (),
+            1 => parts.push(format!("{} voice package", self.voices[0])),
+            _ => parts.push(format!("{} voice packages", self.voices.join(", ")))
+        }
+
+        let mut description = parts.join(", ");
+
+        if !self.ignore.is_empty() {
+            description += &format!(" ignoring {}", self.ignore.join(", "));
+        }
+
+        description
+    }
+}
+
+impl VerificationState {
+    pub fn new(fast: bool, files: usize, broken: Vec<PathBuf>, scope: VerificationScope) -> Self {
+        Self {
+            verified: chrono::Local::now().to_rfc3339(),
+            fast,
+            files,
+            broken,
+            scope
+        }
+    }
+
+    /// Check if this state should replace the previous one
+    /// 
+    /// Verification of a part of the files doesn't tell anything about the rest of them,
+    /// so the state is replaced only by verifications with the same or wider scope.
+    /// Fast verification replaces the full one only if it found broken files
+    pub fn replaces(&self, previous: &Self) -> bool {
+        self.scope.covers(&previous.scope) && (!self.fast || previous.fast || !self.broken.is_empty())
+    }
+
+    /// Combine this state with the previous one
+    /// 
+    /// If this state doesn't replace the previous one, only broken files from `verified` paths
+    /// are updated in the previous state. Fast verification can't tell that files with correct
+    /// sizes are fine, so after the full one it only adds new broken files
+    pub fn merge(self, previous: Self, verified: &[PathBuf]) -> Self {
+        if self.replaces(&previous) {
+            return self;
+        }
+
+        let mut broken = previous.broken;
+
+        if !self.fast || previous.fast {
+            let verified = verified.iter().collect::<HashSet<_>>();
+
+            broken.retain(|path| !verified.contains(path));
+        }
+
+        for path in self.broken {
+            if !broken.contains(&path) {
+                broken.push(path);
+            }
+        }
+
+        Self {
+            broken,
+            ..previous
+        }
+    }
+
+    /// Read state of the last verification. Returns `None` if files were never verified
+    pub fn read() -> anyhow::Result<Option<Self>> {
+        if !Path::new(STATE_FILE).exists() {
+            return Ok(None);
+        }
+
+        let mut file = File::open(STATE_FILE)?;
+        let mut toml = String::new();
+
+        file.read_to_string(&mut toml)?;
+
+        Ok(Some(toml::from_str(&toml)?))
+    }
+
+    pub fn write(&self) -> anyhow::Result<()> {
+        let mut file = File::create(STATE_FILE)?;
+
+        file.write_all(toml::to_string(self)?.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Format verification time in local timezone
+    pub fn verified_at(&self) -> String {
+        match chrono::DateTime::parse_from_rfc3339(&self.verified) {
+            Ok(time) => time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string(),
+            Err(_) => self.verified.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope(game: bool, voices: &[&str], ignore: &[&str]) -> VerificationScope {
+        VerificationScope {
+            game,
+            voices: voices.iter().map(|voice| voice.to_string()).collect(),
+            ignore: ignore.iter().map(|pattern| pattern.to_string()).collect()
+        }
+    }
+
+    fn state(fast: bool, broken: &[&str], scope: VerificationScope) -> VerificationState {
+        VerificationState::new(fast, 0, broken.iter().map(PathBuf::from).collect(), scope)
+    }
+
+    #[test]
+    fn scope_covers() {
+        let full = scope(true, &["English", "Japanese"], &[]);
+
+        assert!(full.covers(&full));
+        assert!(full.covers(&scope(false, &["English"], &[])));
+        assert!(full.covers(&scope(true, &[], &["blk"])));
+
+        assert!(!scope(false, &["English"], &[]).covers(&full));
+        assert!(!scope(true, &["English", "Japanese"], &["blk"]).covers(&full));
+        assert!(!scope(true, &["English"], &[]).covers(&full));
+    }
+
+    #[test]
+    fn replaces_same_or_wider_scope() {
+        let full = scope(true, &["English"], &[]);
+
+        assert!(state(false, &[], full.clone()).replaces(&state(false, &["a"], full.clone())));
+        assert!(state(true, &[], full.clone()).replaces(&state(true, &["a"], full.clone())));
+        assert!(!state(false, &[], scope(false, &["English"], &[])).replaces(&state(false, &["a"], full)));
+    }
+
+    #[test]
+    fn fast_replaces_full_only_with_broken_files() {
+        let full = scope(true, &[], &[]);
+
+        assert!(!state(true, &[], full.clone()).replaces(&state(false, &["a"], full.clone())));
+        assert!(state(true, &["b"], full.clone()).replaces(&state(false, &["a"], full)));
+    }
+
+    #[test]
+    fn narrower_merge_updates_verified_files() {
+        let previous = state(false, &["game.exe", "Audio/English/1.pck"], scope(true, &["English"], &[]));
+        let current = state(false, &["Audio/English/2.pck"], scope(false, &["English"], &[]));
+
+        let verified = [PathBuf::from("Audio/English/1.pck"), PathBuf::from("Audio/English/2.pck")];
+        let merged = current.merge(previous, &verified);
+
+        assert!(merged.scope.game);
+        assert_eq!(merged.broken, vec![PathBuf::from("game.exe"), PathBuf::from("Audio/English/2.pck")]);
+    }
+
+    #[test]
+    fn fast_merge_keeps_hash_mismatches() {
+        let previous = state(false, &["game.exe"], scope(true, &[], &[]));
+        let merged = state(true, &[], scope(true, &[], &[])).merge(previous, &[PathBuf::from("game.exe")]);
+
+        assert!(!merged.fast);
+        assert_eq!(merged.broken, vec![PathBuf::from("game.exe")]);
+    }
+}
@@ -0,0 +1,97 @@
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::io::Write;
+
+use crate::lib::patch::HOSTS_FILE;
+
+/// First line of the entries managed by `patch hosts`
+pub const BEGIN_MARKER: &str = "# BEGIN anime-game-cli telemetry servers";
+
+/// Last line of the entries managed by `patch hosts`
+pub const END_MARKER: &str = "# END anime-game-cli telemetry servers";
+
+/// Get hosts file lines blocking telemetry servers, surrounded by markers
+pub fn entries(servers: &[String]) -> String {
+    let mut entries = vec![BEGIN_MARKER.to_string()];
+
+    for server in servers {
+        entries.push(format!("0.0.0.0 {server}"));
+    }
+
+    entries.push(END_MARKER.to_string());
+
+    entries.join("\n")
+}
+
+/// Check if the hosts file content has entries managed by `patch hosts`
+pub fn has_entries(hosts: &str) -> bool {
+    hosts.lines().any(|line| line.trim() == BEGIN_MARKER)
+}
+
+/// Remove managed entries and telemetry servers blocking lines added by the patch script
+pub fn without_entries(hosts: &str, servers: &[String]) -> String {
+    let mut result = Vec::new();
+    let mut managed = false;
+
+    for line in hosts.lines() {
+        let trimmed = line.trim();
+
+        if trimmed == BEGIN_MARKER {
+            managed = true;
+        }
+
+        else if trimmed == END_MARKER {
+            managed = false;
+        }
+
+        else if !managed && !servers.iter().any(|server| trimmed == format!("0.0.0.0 {server}")) {
+            result.push(line);
+        }
+    }
+
+    let mut result = result.join("\n");
+
+    result.push('\n');
+
+    result
+}
+
+/// Replace managed entries and lines added by the patch script with the new block
+pub fn with_entries(hosts: &str, servers: &[String]) -> String {
+    let mut result = without_entries(hosts, servers);
+
+    result.push_str(&entries(servers));
+    result.push('\n');
+
+    result
+}
+
+/// Write hosts file content to the file
+///
+/// `pkexec tee` is used to write the system hosts file if the current user can't write it
+pub fn write<T: AsRef<Path>>(path: T, content: &str) -> anyhow::Result<()> {
+    let path = path.as_ref();
+
+    if path != Path::new(HOSTS_FILE) || std::fs::OpenOptions::new().append(true).open(path).is_ok() {
+        std::fs::write(path, content)?;
+
+        return Ok(());
+    }
+
+    let mut child = Command::new("pkexec")
+        .arg("tee")
+        .arg(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(content.as_bytes())?;
+    }
+
+    if !child.wait()?.success() {
+        anyhow::bail!("Failed to write {:?} with pkexec", path);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use anime_game_core::genshin::prelude::*;
+use anime_game_core::genshin::repairer::{try_get_integrity_files, try_get_voice_integrity_files};
+use anime_game_core::repairer::IntegrityFile;
+
+use crate::lib::manifest::{self, ManifestFile};
+
+/// Get game integrity files from the manifest if it's specified, or fetch them otherwise
+pub fn game_files(manifest: Option<&[ManifestFile]>) -> anyhow::Result<Vec<IntegrityFile>> {
+    match manifest {
+        Some(files) => Ok(manifest::package_files(files, manifest::GAME_PACKAGE)),
+        None => try_get_integrity_files(None)
+    }
+}
+
+/// Get voice package integrity files from the manifest if it's specified, or fetch them otherwise
+pub fn voice_files(locale: VoiceLocale, manifest: Option<&[ManifestFile]>) -> anyhow::Result<Vec<IntegrityFile>> {
+    match manifest {
+        Some(files) => Ok(manifest::package_files(files, locale.to_code())),
+        None => try_get_voice_integrity_files(locale, None)
+    }
+}
+
+/// List locales of the installed voice packages
+/// 
+/// Packages are listed locally so this works offline as well
+pub fn installed_locales<T: ToString>(game_path: T) -> anyhow::Result<Vec<VoiceLocale>> {
+    Ok(Game::new(game_path).get_voice_packages()?
+        .into_iter()
+        .map(|package| package.locale())
+        .collect())
+}
@@ -0,0 +1,113 @@
+use std::path::Path;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write, Seek, SeekFrom};
+use std::os::unix::io::AsRawFd;
+
+use crate::lib::output::*;
+
+/// Name of the lock file inside of the game folder
+pub const LOCK_FILE: &str = ".anime-game-cli.lock";
+
+/// Advisory lock preventing concurrent modification of the game installation
+/// 
+/// `flock` is held on the lock file which is never removed, so the lock is released
+/// by the system even if the process is killed. The file content is used for messages only
+#[derive(Debug)]
+pub struct Lock {
+    file: File
+}
+
+/// Process which holds the lock
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockOwner {
+    pub pid: u32,
+    pub command: String
+}
+
+impl LockOwner {
+    fn read(file: &mut File) -> Option<Self> {
+        let mut content = String::new();
+
+        file.seek(SeekFrom::Start(0)).ok()?;
+        file.read_to_string(&mut content).ok()?;
+
+        let (pid, command) = content.split_once('\n')?;
+
+        Some(Self {
+            pid: pid.trim().parse().ok()?,
+            command: command.trim().to_string()
+        })
+    }
+
+    fn describe(owner: Option<Self>) -> String {
+        match owner {
+            Some(owner) => format!("PID {} running `{}`", owner.pid, owner.command),
+            None => String::from("another process")
+        }
+    }
+}
+
+/// Call `flock` on the file, retrying if it was interrupted by a signal
+fn flock(file: &File, operation: libc::c_int) -> std::io::Result<()> {
+    loop {
+        if unsafe { libc::flock(file.as_raw_fd(), operation) } == 0 {
+            return Ok(());
+        }
+
+        let err = std::io::Error::last_os_error();
+
+        if err.kind() != std::io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+}
+
+impl Lock {
+    /// Lock the game installation
+    /// 
+    /// If it's already locked by another process, either wait until it's released or return an error
+    pub fn acquire<T: AsRef<Path>>(game_path: T, wait: bool) -> anyhow::Result<Self> {
+        let path = game_path.as_ref().join(LOCK_FILE);
+        let command = std::env::args().skip(1).collect::<Vec<String>>().join(" ");
+
+        std::fs::create_dir_all(game_path.as_ref())?;
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+
+        if let Err(err) = flock(&file, libc::LOCK_EX | libc::LOCK_NB) {
+            if err.raw_os_error() != Some(libc::EWOULDBLOCK) {
+                return Err(err.into());
+            }
+
+            let owner = LockOwner::describe(LockOwner::read(&mut file));
+
+            if !wait {
+                anyhow::bail!("Game is locked by {owner}. Use --wait to wait until it's finished");
+            }
+
+            notice(format!("Waiting for {owner}..."));
+
+            flock(&file, libc::LOCK_EX)?;
+        }
+
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(format!("{}\n{}", std::process::id(), command).as_bytes())?;
+
+        Ok(Self { file })
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        // The file itself is kept so other processes always lock the same inode
+        self.file.set_len(0).ok();
+
+        flock(&self.file, libc::LOCK_UN).ok();
+    }
+}
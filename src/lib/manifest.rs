@@ -0,0 +1,97 @@
+use std::path::{Path, PathBuf};
+use std::fs::File;
+use std::io::{Write, BufRead, BufReader};
+
+use serde::{Serialize, Deserialize};
+
+use anime_game_core::repairer::IntegrityFile;
+
+/// Package name of the game files
+pub const GAME_PACKAGE: &str = "game";
+
+/// Integrity file stored in the manifest
+/// 
+/// `package` is either `game` or voice package code (e.g. `en-us`).
+/// Files without package are considered game files
+#[derive(Debug, Clone)]
+pub struct ManifestFile {
+    pub package: Option<String>,
+    pub file: IntegrityFile
+}
+
+/// Line of the manifest. Uses the same format as `pkg_version` files
+/// with optional package name and base url of the file
+#[derive(Debug, Serialize, Deserialize)]
+struct ManifestLine {
+    #[serde(rename = "remoteName")]
+    remote_name: String,
+
+    md5: String,
+
+    #[serde(rename = "fileSize")]
+    file_size: u64,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    package: Option<String>,
+
+    #[serde(rename = "baseUrl", default, skip_serializing_if = "String::is_empty")]
+    base_url: String
+}
+
+/// Read manifest file
+pub fn read<T: AsRef<Path>>(path: T) -> anyhow::Result<Vec<ManifestFile>> {
+    let mut files = Vec::new();
+
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let line = serde_json::from_str::<ManifestLine>(&line)?;
+
+        files.push(ManifestFile {
+            package: line.package,
+            file: IntegrityFile {
+                path: PathBuf::from(line.remote_name),
+                md5: line.md5,
+                size: line.file_size,
+                base_url: line.base_url
+            }
+        });
+    }
+
+    Ok(files)
+}
+
+/// Write manifest file
+pub fn write<T: AsRef<Path>>(path: T, files: &[ManifestFile]) -> anyhow::Result<()> {
+    let mut manifest = File::create(path)?;
+
+    for file in files {
+        let line = ManifestLine {
+            remote_name: file.file.path.to_string_lossy().to_string(),
+            md5: file.file.md5.clone(),
+            file_size: file.file.size,
+            package: file.package.clone(),
+            base_url: file.file.base_url.clone()
+        };
+
+        manifest.write_all(serde_json::to_string(&line)?.as_bytes())?;
+        manifest.write_all(b"\n")?;
+    }
+
+    Ok(())
+}
+
+/// Get integrity files of the package from the manifest
+pub fn package_files(files: &[ManifestFile], package: &str) -> Vec<IntegrityFile> {
+    files.iter()
+        .filter(|file| match &file.package {
+            Some(name) => name == package,
+            None => package == GAME_PACKAGE
+        })
+        .map(|file| file.file.clone())
+        .collect()
+}
@@ -0,0 +1,249 @@
+use std::path::Path;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::time::Instant;
+
+use serde::{Serialize, Deserialize};
+
+use crate::lib::patch;
+use crate::lib::temp;
+
+/// File with the results of the last `patch mirrors check`
+pub const CACHE_FILE: &str = "mirrors.toml";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MirrorStatus {
+    pub host: String,
+
+    /// Time needed to get the latest revision
+    pub latency_ms: Option<u64>,
+
+    /// Latest revision of the repository
+    pub revision: Option<String>,
+
+    /// Reason why the host is not reachable
+    pub error: Option<String>
+}
+
+impl MirrorStatus {
+    /// Get host's latest revision and measure how much time it took
+    pub fn probe<T: ToString>(host: T) -> Self {
+        let host = host.to_string();
+        let started = Instant::now();
+
+        match patch::remote_revision(&host) {
+            Ok(revision) => Self {
+                host,
+                latency_ms: Some(started.elapsed().as_millis() as u64),
+                revision: Some(revision),
+                error: None
+            },
+            Err(err) => Self {
+                host,
+                latency_ms: None,
+                revision: None,
+                error: Some(err.to_string())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MirrorsCache {
+    /// Time of the check in RFC 3339 format
+    pub checked: String,
+
+    pub mirrors: Vec<MirrorStatus>
+}
+
+impl MirrorsCache {
+    /// Read cached results if they're not older than `ttl` seconds and were made for the same hosts
+    pub fn read(hosts: &[String], ttl: u64) -> Option<Self> {
+        if ttl == 0 || !Path::new(CACHE_FILE).exists() {
+            return None;
+        }
+
+        let mut file = File::open(CACHE_FILE).ok()?;
+        let mut toml = String::new();
+
+        file.read_to_string(&mut toml).ok()?;
+
+        let cache = toml::from_str::<Self>(&toml).ok()?;
+        let checked = chrono::DateTime::parse_from_rfc3339(&cache.checked).ok()?;
+
+        let age = chrono::Local::now().signed_duration_since(checked).num_seconds();
+
+        let same_hosts = cache.mirrors.len() == hosts.len()
+            && cache.mirrors.iter().all(|mirror| hosts.contains(&mirror.host));
+
+        if age >= 0 && (age as u64) < ttl && same_hosts {
+            Some(cache)
+        } else {
+            None
+        }
+    }
+
+    pub fn write(&self) -> anyhow::Result<()> {
+        let mut file = File::create(CACHE_FILE)?;
+
+        file.write_all(toml::to_string(self)?.as_bytes())?;
+
+        Ok(())
+    }
+}
+
+/// Probe all the hosts in parallel and cache the results
+pub fn check(hosts: &[String]) -> Vec<MirrorStatus> {
+    let handlers = hosts.iter()
+        .cloned()
+        .map(|host| std::thread::spawn(move || MirrorStatus::probe(host)))
+        .collect::<Vec<_>>();
+
+    let mirrors = handlers.into_iter()
+        .map(|handler| handler.join().unwrap())
+        .collect::<Vec<_>>();
+
+    let cache = MirrorsCache {
+        checked: chrono::Local::now().to_rfc3339(),
+        mirrors: mirrors.clone()
+    };
+
+    cache.write().ok();
+
+    mirrors
+}
+
+/// Get cached hosts statuses or probe them if the cache is outdated
+pub fn get(hosts: &[String], ttl: u64) -> Vec<MirrorStatus> {
+    match MirrorsCache::read(hosts, ttl) {
+        Some(cache) => cache.mirrors,
+        None => check(hosts)
+    }
+}
+
+/// Get the newest revision of the reachable hosts
+/// 
+/// If hosts have different revisions, they're fetched to the patch repository
+/// (or to a temporary one if the patch is not synced yet) and compared by their history.
+/// Returns `None` if there are no reachable hosts or the newest revision can't be found,
+/// e.g. when histories diverged or fetching failed
+pub fn latest_revision<T: AsRef<Path>>(mirrors: &[MirrorStatus], patch_folder: T) -> Option<String> {
+    let mut revisions: Vec<(&String, &String)> = Vec::new();
+
+    for mirror in mirrors {
+        if let Some(revision) = &mirror.revision {
+            if !revisions.iter().any(|(known, _)| *known == revision) {
+                revisions.push((revision, &mirror.host));
+            }
+        }
+    }
+
+    match revisions.len() {
+        0 => None,
+        1 => Some(revisions[0].0.clone()),
+        _ => {
+            let repository = if patch_folder.as_ref().join(".git").exists() {
+                patch_folder.as_ref().to_path_buf()
+            } else {
+                let repository = temp::staging_folder(None).ok()?.join("mirrors");
+
+                patch::git(".", &["init", "--bare", "--quiet", &repository.to_string_lossy()]).ok()?;
+
+                repository
+            };
+
+            for (revision, host) in &revisions {
+                if patch::git(&repository, &["cat-file", "-e", &format!("{revision}^{{commit}}")]).is_err() {
+                    patch::git(&repository, &["fetch", "--quiet", "--no-tags", host, "HEAD"]).ok()?;
+                }
+            }
+
+            let revisions = revisions.into_iter()
+                .map(|(revision, _)| revision.clone())
+                .collect::<Vec<_>>();
+
+            newest(&revisions, |ancestor, revision| {
+                patch::git(&repository, &["merge-base", "--is-ancestor", ancestor, revision]).is_ok()
+            })
+        }
+    }
+}
+
+/// Find the revision which has all the other ones in its history
+fn newest(revisions: &[String], is_ancestor: impl Fn(&str, &str) -> bool) -> Option<String> {
+    revisions.iter()
+        .find(|newest| revisions.iter().all(|revision| revision == *newest || is_ancestor(revision, newest)))
+        .cloned()
+}
+
+/// Sort hosts from the healthiest one: hosts with the latest revision first, then by latency.
+/// Unreachable hosts are removed
+/// 
+/// If the latest revision is not known, hosts are sorted by latency only
+pub fn rank(mirrors: &[MirrorStatus], latest: Option<&String>) -> Vec<MirrorStatus> {
+    let mut ranked = mirrors.iter()
+        .filter(|mirror| mirror.revision.is_some())
+        .cloned()
+        .collect::<Vec<_>>();
+
+    ranked.sort_by_key(|mirror| (latest.is_some() && mirror.revision.as_ref() != latest, mirror.latency_ms));
+
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mirror(host: &str, latency_ms: Option<u64>, revision: Option<&str>) -> MirrorStatus {
+        MirrorStatus {
+            host: host.to_string(),
+            latency_ms,
+            revision: revision.map(String::from),
+            error: None
+        }
+    }
+
+    // c <- b <- a, and d diverged from c
+    fn is_ancestor(ancestor: &str, revision: &str) -> bool {
+        matches!((ancestor, revision), ("c", "b" | "a" | "d") | ("b", "a"))
+    }
+
+    fn revisions(revisions: &[&str]) -> Vec<String> {
+        revisions.iter().map(|revision| revision.to_string()).collect()
+    }
+
+    #[test]
+    fn newest_by_history_not_majority() {
+        assert_eq!(newest(&revisions(&["b", "a"]), is_ancestor).as_deref(), Some("a"));
+        assert_eq!(newest(&revisions(&["c", "a", "b"]), is_ancestor).as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn newest_of_diverged_is_unknown() {
+        assert_eq!(newest(&revisions(&["a", "d"]), is_ancestor), None);
+    }
+
+    #[test]
+    fn single_revision_is_latest() {
+        let mirrors = [mirror("first", Some(20), Some("a")), mirror("second", None, None), mirror("third", Some(10), Some("a"))];
+
+        assert_eq!(latest_revision(&mirrors, "/nonexistent").as_deref(), Some("a"));
+        assert_eq!(latest_revision(&mirrors[1..2], "/nonexistent"), None);
+    }
+
+    #[test]
+    fn rank_latest_first() {
+        let mirrors = [
+            mirror("outdated", Some(10), Some("b")),
+            mirror("unreachable", None, None),
+            mirror("slow", Some(300), Some("a")),
+            mirror("fast", Some(50), Some("a"))
+        ];
+
+        let hosts = |ranked: Vec<MirrorStatus>| ranked.into_iter().map(|mirror| mirror.host).collect::<Vec<_>>();
+
+        assert_eq!(hosts(rank(&mirrors, Some(&String::from("a")))), vec!["fast", "slow", "outdated"]);
+        assert_eq!(hosts(rank(&mirrors, None)), vec!["outdated", "fast", "slow"]);
+    }
+}
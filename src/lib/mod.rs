@@ -1,8 +1,191 @@
+use std::path::{Path, PathBuf};
+
 pub mod config;
 pub mod output;
 pub mod command_traits;
+pub mod orphans;
+pub mod quarantine;
+pub mod hash;
+pub mod throttle;
+pub mod storage;
+pub mod source;
+pub mod manifest;
+pub mod integrity;
+pub mod health;
+pub mod space;
+pub mod temp;
+pub mod lock;
+pub mod snapshot;
+pub mod patch;
+pub mod patch_backup;
+pub mod hosts;
+pub mod mirrors;
+pub mod signature;
 
 /// Convert bytes to gigabytes with 2 digits round
 pub fn format_size(bytes: u64) -> f64 {
     (bytes as f64 / 1024.0 / 1024.0 / 1024.0 * 100.0).ceil() / 100.0
 }
+
+/// Get positional arguments skipping flags and their values
+/// 
+/// `value_args` are names and aliases of the arguments which take a value.
+/// Only their next word is skipped if the value is not passed after `=`
+pub fn positional_args(args: &[String], value_args: &[&str]) -> Vec<String> {
+    let mut positional = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        if arg.starts_with('-') {
+            if !arg.contains('=') && value_args.contains(&arg.as_str()) {
+                args.next();
+            }
+        }
+
+        else {
+            positional.push(arg.clone());
+        }
+    }
+
+    positional
+}
+
+/// Recursively list all the files inside of the folder
+/// 
+/// Returned paths are relative to this folder. Symlinks are not followed
+pub fn list_files<T: AsRef<Path>>(path: T) -> std::io::Result<Vec<PathBuf>> {
+    let path = path.as_ref();
+
+    let mut files = Vec::new();
+    let mut folders = vec![PathBuf::new()];
+
+    while let Some(folder) = folders.pop() {
+        for entry in std::fs::read_dir(path.join(&folder))? {
+            let entry = entry?;
+            let relative = folder.join(entry.file_name());
+
+            if entry.file_type()?.is_dir() {
+                folders.push(relative);
+            } else {
+                files.push(relative);
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+/// Calculate total size of the files inside of the folder
+pub fn folder_size<T: AsRef<Path>>(path: T) -> std::io::Result<u64> {
+    let path = path.as_ref();
+
+    if path.is_file() {
+        return Ok(std::fs::metadata(path)?.len());
+    }
+
+    let mut size = 0;
+
+    for file in list_files(path)? {
+        size += std::fs::symlink_metadata(path.join(file))?.len();
+    }
+
+    Ok(size)
+}
+
+/// Move file to the new location, creating parent folders if needed
+/// 
+/// Falls back to copying when the file can't be renamed (e.g. is on another filesystem)
+pub fn move_file<T: AsRef<Path>, F: AsRef<Path>>(from: T, to: F) -> std::io::Result<()> {
+    if let Some(parent) = to.as_ref().parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    if std::fs::rename(from.as_ref(), to.as_ref()).is_err() {
+        std::fs::copy(from.as_ref(), to.as_ref())?;
+        std::fs::remove_file(from.as_ref())?;
+    }
+
+    Ok(())
+}
+
+/// Copy the file, creating parent folders if needed
+/// 
+/// The copy is made as a reflink when the filesystem supports it, so it doesn't take
+/// additional space until one of the files is changed
+pub fn copy_file<T: AsRef<Path>, F: AsRef<Path>>(from: T, to: F) -> std::io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    // FICLONE ioctl request from linux/fs.h
+    const FICLONE: libc::c_ulong = 0x40049409;
+
+    if let Some(parent) = to.as_ref().parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let source = std::fs::File::open(from.as_ref())?;
+    let target = std::fs::File::create(to.as_ref())?;
+
+    if unsafe { libc::ioctl(target.as_raw_fd(), FICLONE as _, source.as_raw_fd()) } == 0 {
+        target.set_permissions(source.metadata()?.permissions())?;
+
+        return Ok(());
+    }
+
+    drop(target);
+
+    std::fs::copy(from.as_ref(), to.as_ref())?;
+
+    Ok(())
+}
+
+/// Recursively copy the folder content, creating the target folder if needed
+/// 
+/// Symlinks are copied as symlinks
+pub fn copy_folder<T: AsRef<Path>, F: AsRef<Path>>(from: T, to: F) -> std::io::Result<()> {
+    for file in list_files(from.as_ref())? {
+        let source = from.as_ref().join(&file);
+        let target = to.as_ref().join(&file);
+
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        if std::fs::symlink_metadata(&source)?.file_type().is_symlink() {
+            std::os::unix::fs::symlink(std::fs::read_link(&source)?, &target)?;
+        } else {
+            std::fs::copy(&source, &target)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn positional_args_skip_option_values() {
+        let args = args(&["english", "-t", "8", "--fast", "japanese", "--temp-dir", "/tmp/staging", "korean"]);
+
+        assert_eq!(positional_args(&args, &["--threads", "-t", "--temp-dir"]), vec!["english", "japanese", "korean"]);
+    }
+
+    #[test]
+    fn positional_args_keep_words_after_flags() {
+        let args = args(&["--fast", "english", "--verify", "japanese"]);
+
+        assert_eq!(positional_args(&args, &["--threads", "-t"]), vec!["english", "japanese"]);
+    }
+
+    #[test]
+    fn positional_args_keep_word_after_inline_value() {
+        let args = args(&["--threads=8", "english"]);
+
+        assert_eq!(positional_args(&args, &["--threads"]), vec!["english"]);
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anime_game_core::repairer::IntegrityFile;
+
+use crate::lib::list_files;
+
+/// Files which are not listed in integrity files but still shouldn't be considered orphans
+/// 
+/// - `name` matches the file name
+/// - `*suffix` matches the end of the file name
+/// - `folder/` matches every file inside of any folder with this name
+pub const ALLOWLIST: &[&str] = &[
+    // Configs
+    "config.ini",
+    "launcher.bat",
+    ".version",
+    "pkg_version",
+    "*_pkg_version",
+    ".anime-game-cli.lock",
+
+    // Backup of the previous game version
+    ".update-backup/",
+
+    // Logs and crash reports
+    "*.log",
+    "*.dmp",
+
+    // Screenshots
+    "ScreenShot/",
+
+    // Patch files
+    ".patch-backup/",
+    "*.bak",
+    "*.vcdiff",
+    "*.patched",
+
+    // Runtime data created by the game itself
+    "Persistent/",
+    "webCaches/",
+    "SDKCaches/"
+];
+
+/// Check if the file is allowed to not be listed in integrity files
+pub fn is_allowed<T: AsRef<Path>>(path: T) -> bool {
+    let path = path.as_ref();
+
+    let name = match path.file_name() {
+        Some(name) => name.to_string_lossy().to_lowercase(),
+        None => return false
+    };
+
+    for rule in ALLOWLIST {
+        let rule = rule.to_lowercase();
+
+        let allowed = if let Some(folder) = rule.strip_suffix('/') {
+            path.parent()
+                .map(|parent| parent.iter().any(|component| component.to_string_lossy().to_lowercase() == folder))
+                .unwrap_or(false)
+        } else if let Some(suffix) = rule.strip_prefix('*') {
+            name.ends_with(suffix)
+        } else {
+            name == rule
+        };
+
+        if allowed {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Find files inside the game folder that are not listed in the integrity files
+/// 
+/// Returns relative paths of these files with their sizes
+pub fn find<T: AsRef<Path>>(game_path: T, files: &[IntegrityFile]) -> std::io::Result<Vec<(PathBuf, u64)>> {
+    let game_path = game_path.as_ref();
+
+    let known = files.iter()
+        .map(|file| file.path.clone())
+        .collect::<HashSet<PathBuf>>();
+
+    let mut orphans = Vec::new();
+
+    for path in list_files(game_path)? {
+        if !known.contains(&path) && !is_allowed(&path) {
+            let size = std::fs::metadata(game_path.join(&path))
+                .map(|metadata| metadata.len())
+                .unwrap_or(0);
+
+            orphans.push((path, size));
+        }
+    }
+
+    Ok(orphans)
+}
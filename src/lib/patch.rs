@@ -0,0 +1,782 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anime_game_core::version::Version;
+use anime_game_core::version::ToVersion;
+use anime_game_core::genshin::prelude::{Game, Patch, PatchApplier};
+
+use crate::lib::hash;
+use crate::lib::patch_backup;
+use crate::lib::signature;
+use crate::lib::output::*;
+use crate::lib::config::Config;
+use crate::lib::copy_folder;
+
+/// System hosts file
+pub const HOSTS_FILE: &str = "/etc/hosts";
+
+/// Extension of the file next to the patch folder with the source the patch was synced from
+///
+/// It's stored outside of the patch folder so it doesn't change the patch repository working tree
+pub const ORIGIN_EXTENSION: &str = "origin";
+
+/// Run git command and get its trimmed output
+pub fn git<T: AsRef<Path>>(folder: T, args: &[&str]) -> anyhow::Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(folder.as_ref())
+        .args(args)
+        .output()?;
+
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Get revision of the synced local patch repository
+pub fn local_revision<T: AsRef<Path>>(patch_folder: T) -> anyhow::Result<String> {
+    git(patch_folder, &["rev-parse", "HEAD"])
+}
+
+/// Get full commit hash of the revision (commit, tag or branch) in the local patch repository
+pub fn resolve_revision<T: AsRef<Path>>(patch_folder: T, revision: &str) -> anyhow::Result<String> {
+    git(patch_folder, &["rev-parse", "--verify", &format!("{revision}^{{commit}}")])
+}
+
+/// Switch local patch repository to the revision
+///
+/// Branches are taken from the synced remote repository since local ones can be outdated
+pub fn checkout<T: AsRef<Path>>(patch_folder: T, revision: &str) -> anyhow::Result<()> {
+    let commit = resolve_revision(patch_folder.as_ref(), &format!("refs/remotes/origin/{revision}"))
+        .or_else(|_| resolve_revision(patch_folder.as_ref(), revision))?;
+
+    git(patch_folder, &["checkout", "--quiet", "--detach", &commit])?;
+
+    Ok(())
+}
+
+/// Get revision the patch should be pinned to: passed one or `patch.revision` config value
+pub fn pinned_revision(config: &Config, revision: Option<String>) -> Option<String> {
+    revision.or_else(|| {
+        if config.patch.revision.is_empty() {
+            None
+        } else {
+            Some(config.patch.revision.clone())
+        }
+    })
+}
+
+/// Check if the local patch is synced
+///
+/// If the patch is pinned to some revision, it's synced when this revision is checked out.
+/// For branches it's their latest commit in the remote repositories.
+/// Patch synced from a local source is synced as it is. Otherwise it should have
+/// the same revision as the remote repositories
+pub fn is_synced(config: &Config) -> anyhow::Result<bool> {
+    match pinned_revision(config, None) {
+        // Branch can get new commits so its remote state is checked when hosts are reachable
+        Some(revision) => match remote_branch(&config.patch.hosts, &revision).map(Ok).unwrap_or_else(|| resolve_revision(&config.paths.patch, &revision)) {
+            Ok(commit) => Ok(local_revision(&config.paths.patch)? == commit),
+            Err(_) => Ok(false)
+        },
+
+        // Remote hosts may not be reachable at all, that's why the local source was used
+        None if is_local_origin(&config.paths.patch) => Ok(Path::new(&config.paths.patch).exists()),
+
+        None => PatchApplier::new(&config.paths.patch).is_sync(&config.patch.hosts)
+    }
+}
+
+/// Sync the patch with the first working host and switch it to the pinned revision
+///
+/// Returns the host the patch was synced with
+pub fn sync(config: &Config, hosts: &[String], revision: Option<&str>) -> anyhow::Result<String> {
+    let applier = PatchApplier::new(&config.paths.patch);
+
+    for host in hosts {
+        match applier.sync(host) {
+            Ok(true) => {
+                if let Err(err) = write_origin(&config.paths.patch, host) {
+                    warn(format!("Failed to save patch origin: {err}"));
+                }
+
+                if let Some(revision) = revision {
+                    if let Err(err) = checkout(&config.paths.patch, revision) {
+                        anyhow::bail!("Failed to switch patch to {revision} revision: {err}");
+                    }
+                }
+
+                return Ok(host.clone());
+            },
+            Ok(false) => warn(format!("Failed to sync repo {host}")),
+            Err(err) => warn(format!("Failed to sync repo {host}: {err}"))
+        }
+    }
+
+    anyhow::bail!("Failed to sync patch")
+}
+
+/// Check if the patch info describes the checked out patch revision
+///
+/// Patch status and version are fetched from the latest revision of the remote repository.
+/// If another revision is checked out (e.g. the patch is pinned), its patch for this version
+/// should be the same as in the latest revision. Otherwise the info can't be used for it
+pub fn check_revision(config: &Config, patch_info: &Patch) -> anyhow::Result<()> {
+    let (version, host) = match patch_info {
+        Patch::Testing { version, host, .. } |
+        Patch::Available { version, host, .. } => (*version, host),
+
+        _ => return Ok(())
+    };
+
+    let local = match local_revision(&config.paths.patch) {
+        Ok(local) => local,
+
+        // Patch synced from an archive without git repository can't be pinned
+        Err(_) if pinned_revision(config, None).is_none() => return Ok(()),
+        Err(err) => anyhow::bail!("Failed to get checked out patch revision: {err}")
+    };
+
+    let latest = remote_revision(host)?;
+
+    if local == latest {
+        return Ok(());
+    }
+
+    if resolve_revision(&config.paths.patch, &latest).is_err() {
+        anyhow::bail!("Patch info was fetched for {latest} revision which is not in the local repository. Run `patch sync` to fetch it");
+    }
+
+    let same = Command::new("git")
+        .arg("-C")
+        .arg(&config.paths.patch)
+        .args(["diff", "--quiet", &latest, &local, "--", &version.to_plain_string()])
+        .status()?
+        .success();
+
+    if !same {
+        anyhow::bail!("Patch for {version} in the checked out {local} revision differs from the latest {latest} revision the patch info was fetched for");
+    }
+
+    Ok(())
+}
+
+/// Back up game files modified by the patch and apply it
+///
+/// If `patch.require_signature` is enabled, the patch revision should be signed by one of the trusted keys.
+/// Returns path to the backup
+pub fn apply(config: &Config, patch_info: Patch, root: bool) -> anyhow::Result<PathBuf> {
+    let version = match patch_info.to_version() {
+        Some(version) => version,
+        None => anyhow::bail!("Patch is not available")
+    };
+
+    if let Err(err) = check_revision(config, &patch_info) {
+        anyhow::bail!("Refusing to apply the patch: {err}");
+    }
+
+    let script = match PatchScript::read(&config.paths.patch, version) {
+        Ok(script) => script,
+        Err(err) => anyhow::bail!("Failed to read patch script: {err}")
+    };
+
+    // Patch script is run with root privileges so it should be made by trusted people
+    if config.patch.require_signature {
+        match signature::verify(&config.paths.patch, &config.patch.trusted_keys) {
+            Ok(signature) => notice(format!("Patch signature verified: {signature}")),
+            Err(err) => anyhow::bail!("Refusing to apply the patch: {err}")
+        }
+    }
+
+    let backup = match patch_backup::create(&config.paths.game, version, &script) {
+        Ok(backup) => backup,
+        Err(err) => anyhow::bail!("Failed to back up game files: {err}")
+    };
+
+    PatchApplier::new(&config.paths.patch).apply(&config.paths.game, patch_info, root)?;
+
+    Ok(backup)
+}
+
+/// Sync and apply the patch if it's not applied to the game
+///
+/// Testing patch is applied only if `patch.allow_testing` is enabled.
+/// Root is not requested if telemetry servers are blocked by `patch.isolated_hosts`
+pub fn auto_apply(config: &Config, patch_info: &Patch) -> anyhow::Result<()> {
+    match patch_info {
+        Patch::Available { .. } => (),
+        Patch::Testing { .. } if config.patch.allow_testing => (),
+
+        Patch::Testing { version, .. } => anyhow::bail!("Patch for {version} is in testing stage. Set patch.allow_testing = true to apply it automatically"),
+        Patch::Preparation { version, .. } => anyhow::bail!("Patch for {version} is not released yet"),
+        Patch::Outdated { current, latest, .. } => anyhow::bail!("Patch is outdated ({current} -> {latest})"),
+        Patch::NotAvailable => anyhow::bail!("Patch is not available")
+    }
+
+    if patch_info.is_applied(&config.paths.game)? {
+        return Ok(());
+    }
+
+    if !is_synced(config)? {
+        notice("Syncing patch...");
+
+        sync(config, &config.patch.hosts, pinned_revision(config, None).as_deref())?;
+    }
+
+    notice("Applying patch...");
+
+    apply(config, patch_info.clone(), !config.patch.isolated_hosts)?;
+
+    notice("Patch successfully applied");
+
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct Revision {
+    pub commit: String,
+
+    /// Commit date in `YYYY-MM-DD` format
+    pub date: String,
+
+    /// Tags and branches pointing to the commit
+    pub refs: String,
+
+    pub message: String
+}
+
+/// Get the latest revisions of the local patch repository
+///
+/// Pinned patch lists history of the checked out revision. Otherwise it's the history
+/// of the remote default branch, or of the checked out revision if it's not known
+pub fn log<T: AsRef<Path>>(patch_folder: T, pinned: bool, count: usize) -> anyhow::Result<Vec<Revision>> {
+    let patch_folder = patch_folder.as_ref();
+
+    let revision = if !pinned && git(patch_folder, &["rev-parse", "--verify", "--quiet", "refs/remotes/origin/HEAD"]).is_ok() {
+        "origin/HEAD"
+    } else {
+        "HEAD"
+    };
+
+    let output = git(patch_folder, &["log", revision, &format!("--max-count={count}"), "--format=%H%x09%cs%x09%D%x09%s"])?;
+
+    Ok(output.lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(4, '\t');
+
+            Some(Revision {
+                commit: parts.next()?.to_string(),
+                date: parts.next()?.to_string(),
+                refs: parts.next()?.to_string(),
+                message: parts.next().unwrap_or_default().to_string()
+            })
+        })
+        .collect())
+}
+
+/// Get latest revision of the remote patch repository
+pub fn remote_revision<T: AsRef<str>>(host: T) -> anyhow::Result<String> {
+    let output = git(".", &["ls-remote", host.as_ref(), "HEAD"])?;
+
+    match output.split_whitespace().next() {
+        Some(revision) => Ok(revision.to_string()),
+        None => anyhow::bail!("Repository has no HEAD revision")
+    }
+}
+
+/// Get the latest commit of the branch from the first reachable remote repository
+///
+/// Returns `None` if the revision is not a branch or all the hosts are unreachable
+pub fn remote_branch(hosts: &[String], branch: &str) -> Option<String> {
+    for host in hosts {
+        if let Ok(output) = git(".", &["ls-remote", "--heads", host, &format!("refs/heads/{branch}")]) {
+            return output.split_whitespace().next().map(String::from);
+        }
+    }
+
+    None
+}
+
+/// Info parsed from the patch script of the specific game version
+#[derive(Debug, Clone, Default)]
+pub struct PatchScript {
+    /// Path to the script
+    pub path: PathBuf,
+
+    /// Game files modified by the patch, relative to the game folder
+    pub files: Vec<String>,
+
+    /// Hashes of the original game files the patch can be applied to
+    /// 
+    /// Every hash is associated with the game file mentioned last before it, if there's one
+    pub hashes: Vec<(Option<String>, String)>,
+
+    /// Telemetry servers blocked in the hosts file
+    pub servers: Vec<String>
+}
+
+impl PatchScript {
+    /// Read `patch.sh` script of the game version from the synced patch folder
+    pub fn read<T: AsRef<Path>>(patch_folder: T, version: Version) -> anyhow::Result<Self> {
+        let path = patch_folder.as_ref()
+            .join(version.to_plain_string())
+            .join("patch.sh");
+
+        let mut script = Self::parse(&std::fs::read_to_string(&path)?);
+
+        script.path = path;
+
+        Ok(script)
+    }
+
+    /// Parse patch script content
+    pub fn parse(script: &str) -> Self {
+        let mut info = Self::default();
+
+        // Scripts calculate hash of the file and compare it on the next lines
+        let mut last_file = None;
+
+        for line in script.lines() {
+            let line = line.trim();
+
+            if line.starts_with('#') {
+                continue;
+            }
+
+            // echo "0.0.0.0 log-upload-os.example.com" >> /etc/hosts
+            if let Some(start) = line.find("0.0.0.0 ") {
+                if let Some(server) = line[start + 8..].split(|c: char| c.is_whitespace() || c == '"' || c == '\'').next() {
+                    if !server.is_empty() && !info.servers.iter().any(|known| known == server) {
+                        info.servers.push(server.to_string());
+                    }
+                }
+
+                continue;
+            }
+
+            for word in line.split(|c: char| c.is_whitespace() || "=();[]".contains(c)) {
+                let word = word.replace(['"', '\''], "");
+                let lowercase = word.to_lowercase();
+
+                if lowercase.ends_with(".dll") || lowercase.ends_with(".exe") {
+                    if let Some(file) = Self::game_file(&word) {
+                        if !info.files.contains(&file) {
+                            info.files.push(file.clone());
+                        }
+
+                        last_file = Some(file);
+                    }
+                }
+
+                else if word.len() == 32 && word.chars().all(|c| c.is_ascii_hexdigit()) {
+                    let hash = (last_file.clone(), lowercase);
+
+                    if !info.hashes.contains(&hash) {
+                        info.hashes.push(hash);
+                    }
+                }
+            }
+        }
+
+        info
+    }
+
+    /// Get path relative to the game folder from the path used in the script
+    ///
+    /// Script is run from the game folder so relative paths are kept as they are, and game folder
+    /// variables like `$GAME_PATH/` are stripped. Paths with other variables (e.g. patch folder)
+    /// and absolute paths don't point to the game files
+    fn game_file(word: &str) -> Option<String> {
+        let mut path = word;
+
+        if let Some(variable) = path.strip_prefix('$') {
+            let (name, rest) = match variable.strip_prefix('{') {
+                Some(variable) => variable.split_once('}')?,
+                None => variable.split_at(variable.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(variable.len()))
+            };
+
+            if !name.to_uppercase().contains("GAME") {
+                return None;
+            }
+
+            path = rest.strip_prefix('/')?;
+        }
+
+        while let Some(rest) = path.strip_prefix("./") {
+            path = rest;
+        }
+
+        if path.is_empty() || path.starts_with('/') || path.contains('$') || path.split('/').any(|part| part == "..") {
+            None
+        } else {
+            Some(path.to_string())
+        }
+    }
+
+    /// Get expected hashes of the original game file
+    /// 
+    /// Hashes not associated with any file are returned if the file has no own ones
+    pub fn expected_hashes(&self, file: &str) -> Vec<&str> {
+        let hashes = self.hashes.iter()
+            .filter(|(hash_file, _)| hash_file.as_deref() == Some(file))
+            .map(|(_, hash)| hash.as_str())
+            .collect::<Vec<&str>>();
+
+        if !hashes.is_empty() {
+            return hashes;
+        }
+
+        self.hashes.iter()
+            .filter(|(hash_file, _)| hash_file.is_none())
+            .map(|(_, hash)| hash.as_str())
+            .collect()
+    }
+
+    /// Read `patch.sh` script of the newest game version available in the synced patch folder
+    pub fn latest<T: AsRef<Path>>(patch_folder: T) -> anyhow::Result<Self> {
+        let mut latest: Option<(u64, PathBuf)> = None;
+
+        for entry in std::fs::read_dir(patch_folder.as_ref())? {
+            let path = entry?.path();
+
+            let version = path.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.parse::<u64>().ok());
+
+            if let Some(version) = version {
+                if path.join("patch.sh").exists() && latest.as_ref().map(|(latest, _)| version > *latest).unwrap_or(true) {
+                    latest = Some((version, path));
+                }
+            }
+        }
+
+        match latest {
+            Some((_, path)) => {
+                let mut script = Self::parse(&std::fs::read_to_string(path.join("patch.sh"))?);
+
+                script.path = path.join("patch.sh");
+
+                Ok(script)
+            },
+            None => anyhow::bail!("No patch scripts found in {:?}", patch_folder.as_ref())
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileStatus {
+    /// Original file was backed up by the patch script
+    Patched,
+
+    /// File's hash is one of its original hashes known by the patch script
+    Original,
+
+    /// File exists but its state can't be determined
+    Unknown,
+
+    Missing
+}
+
+impl FileStatus {
+    pub fn to_name(&self) -> &str {
+        match self {
+            Self::Patched => "patched",
+            Self::Original => "original",
+            Self::Unknown => "unknown",
+            Self::Missing => "missing"
+        }
+    }
+}
+
+/// Get patching status of the game file
+pub fn file_status<T: AsRef<Path>>(game_path: T, file: &str, script: &PatchScript) -> FileStatus {
+    let path = game_path.as_ref().join(file);
+
+    if !path.exists() {
+        FileStatus::Missing
+    }
+
+    // Patch script renames original files to *.bak
+    else if game_path.as_ref().join(format!("{file}.bak")).exists() {
+        FileStatus::Patched
+    }
+
+    else {
+        match hash::md5(&path) {
+            Ok(hash) if script.expected_hashes(file).contains(&hash.to_lowercase().as_str()) => FileStatus::Original,
+            _ => FileStatus::Unknown
+        }
+    }
+}
+
+/// Check if the patch for the installed game version is applied without fetching patch info
+///
+/// All the files modified by the patch script should have their original copies
+pub fn is_applied_locally(config: &Config) -> anyhow::Result<bool> {
+    let version = Game::new(&config.paths.game).try_get_version()?;
+    let script = PatchScript::read(&config.paths.patch, version)?;
+
+    if script.files.is_empty() {
+        anyhow::bail!("Patch script {:?} doesn't modify any game files", script.path);
+    }
+
+    Ok(script.files.iter().all(|file| file_status(&config.paths.game, file, &script) == FileStatus::Patched))
+}
+
+/// Check which servers are blocked in the system hosts file
+pub fn blocked_servers(servers: &[String]) -> anyhow::Result<Vec<(String, bool)>> {
+    Ok(blocked_in(&std::fs::read_to_string(HOSTS_FILE)?, servers))
+}
+
+/// Check which servers are blocked in the hosts file content
+fn blocked_in(hosts: &str, servers: &[String]) -> Vec<(String, bool)> {
+    let blocked = hosts.lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .flat_map(|line| {
+            let mut words = line.split_whitespace();
+
+            // One address can be followed by several hostnames
+            match words.next() {
+                Some("0.0.0.0" | "127.0.0.1") => words.map(String::from).collect(),
+                _ => Vec::new()
+            }
+        })
+        .collect::<Vec<String>>();
+
+    servers.iter()
+        .map(|server| (server.clone(), blocked.contains(server)))
+        .collect()
+}
+
+/// Get path to the file with the source the patch was synced from
+fn origin_file<T: AsRef<Path>>(patch_folder: T) -> PathBuf {
+    let patch_folder = patch_folder.as_ref();
+
+    // File name is used so trailing slashes don't put the file inside of the folder
+    match (patch_folder.parent(), patch_folder.file_name()) {
+        (Some(parent), Some(name)) => parent.join(format!("{}.{ORIGIN_EXTENSION}", name.to_string_lossy())),
+        _ => patch_folder.with_extension(ORIGIN_EXTENSION)
+    }
+}
+
+/// Get the source the patch was synced from
+pub fn read_origin<T: AsRef<Path>>(patch_folder: T) -> Option<String> {
+    std::fs::read_to_string(origin_file(patch_folder))
+        .ok()
+        .map(|origin| origin.trim().to_string())
+}
+
+/// Remember the source the patch was synced from
+pub fn write_origin<T: AsRef<Path>>(patch_folder: T, origin: &str) -> std::io::Result<()> {
+    std::fs::write(origin_file(patch_folder), origin)
+}
+
+/// Check if the patch was synced from a local folder, archive or git bundle
+pub fn is_local_origin<T: AsRef<Path>>(patch_folder: T) -> bool {
+    read_origin(patch_folder)
+        .map(|origin| Path::new(&origin).is_absolute())
+        .unwrap_or(false)
+}
+
+/// Populate patch folder from a local folder, `.tar.gz` archive or git bundle
+///
+/// The source is unpacked to the staging folder and checked to have patch scripts
+/// before replacing the current patch folder
+pub fn sync_from<T: AsRef<Path>, F: AsRef<Path>, S: AsRef<Path>>(source: T, patch_folder: F, staging_folder: S) -> anyhow::Result<()> {
+    let source = source.as_ref();
+    let patch_folder = patch_folder.as_ref();
+
+    let mut unpacked = staging_folder.as_ref().join("patch");
+
+    if unpacked.exists() {
+        std::fs::remove_dir_all(&unpacked)?;
+    }
+
+    let name = source.to_string_lossy().to_lowercase();
+
+    if source.is_dir() {
+        copy_folder(source, &unpacked)?;
+    }
+
+    else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        std::fs::create_dir_all(&unpacked)?;
+
+        let status = Command::new("tar")
+            .arg("-xzf")
+            .arg(source)
+            .arg("-C")
+            .arg(&unpacked)
+            .status()?;
+
+        if !status.success() {
+            anyhow::bail!("Failed to unpack {:?}", source);
+        }
+
+        // Archives usually have a single root folder
+        let entries = std::fs::read_dir(&unpacked)?.collect::<Result<Vec<_>, _>>()?;
+
+        if let [entry] = &entries[..] {
+            if entry.path().is_dir() {
+                unpacked = entry.path();
+            }
+        }
+    }
+
+    else if name.ends_with(".bundle") {
+        let output = Command::new("git")
+            .arg("clone")
+            .arg(source)
+            .arg(&unpacked)
+            .output()?;
+
+        if !output.status.success() {
+            anyhow::bail!("Failed to clone {:?}: {}", source, String::from_utf8_lossy(&output.stderr).trim());
+        }
+    }
+
+    else {
+        anyhow::bail!("Unsupported patch source {:?}. It should be a folder, .tar.gz archive or git bundle", source);
+    }
+
+    match PatchScript::latest(&unpacked) {
+        Ok(script) if script.files.is_empty() => anyhow::bail!("{:?} doesn't look like a patch: {:?} doesn't modify any game files", source, script.path),
+        Ok(_) => (),
+        Err(err) => anyhow::bail!("{:?} doesn't look like a patch: {}", source, err)
+    }
+
+    // Copied or cloned repositories should be readable by git to check their revisions and signatures
+    if unpacked.join(".git").exists() {
+        if let Err(err) = local_revision(&unpacked) {
+            anyhow::bail!("{:?} has a broken git repository: {}", source, err);
+        }
+    }
+
+    if let Some(parent) = patch_folder.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    // Current patch is kept next to the new one until it's moved successfully,
+    // because it can't be fetched again on offline machines
+    let previous = patch_folder.with_file_name(format!("{}.old", patch_folder.file_name().unwrap_or_default().to_string_lossy()));
+
+    if previous.exists() {
+        std::fs::remove_dir_all(&previous)?;
+    }
+
+    if patch_folder.exists() {
+        std::fs::rename(patch_folder, &previous)?;
+    }
+
+    let moved = match std::fs::rename(&unpacked, patch_folder) {
+        Ok(()) => Ok(()),
+        Err(_) => copy_folder(&unpacked, patch_folder)
+    };
+
+    std::fs::remove_dir_all(staging_folder.as_ref().join("patch")).ok();
+
+    if let Err(err) = moved {
+        std::fs::remove_dir_all(patch_folder).ok();
+
+        if previous.exists() {
+            std::fs::rename(&previous, patch_folder)?;
+        }
+
+        anyhow::bail!("Failed to move the patch to {:?}: {}. Previous patch was kept", patch_folder, err);
+    }
+
+    if previous.exists() {
+        std::fs::remove_dir_all(&previous)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = r#"#!/bin/bash
+# Run this script from the game folder
+DIR=$(dirname "$(readlink -f "$0")")
+
+if [[ ! -e "UnityPlayer.dll" ]]; then
+    echo "Game files not found"
+    exit 1
+fi
+
+sum=($(md5sum UnityPlayer.dll))
+if [ "${sum}" != "9D3A1EC2F9B2A5C44E9F7C4B1FE3A6D0" ]; then
+    echo "Wrong UnityPlayer.dll hash"
+fi
+
+xdelta3 -d -s "UnityPlayer.dll" "$DIR/unityplayer.vcdiff" "UnityPlayer.dll.patched"
+mv -f "UnityPlayer.dll" "UnityPlayer.dll.bak"
+xdelta3 -d -s "${GAME_PATH}/GenshinImpact_Data/Plugins/xlua.dll" "$DIR/xlua.vcdiff" "./GenshinImpact_Data/Plugins/xlua.dll.patched"
+cp "$DIR/launcher.exe" /usr/local/bin/launcher.exe
+cp "../outside.dll" "$HOME/copy.dll"
+
+echo "0.0.0.0 log-upload-os.example.com" >> /etc/hosts
+echo '0.0.0.0 overseauspider.example.com' >> /etc/hosts
+"#;
+
+    #[test]
+    fn parse_game_files() {
+        let script = PatchScript::parse(SCRIPT);
+
+        assert_eq!(script.files, vec!["UnityPlayer.dll", "GenshinImpact_Data/Plugins/xlua.dll"]);
+    }
+
+    #[test]
+    fn parse_hashes_and_servers() {
+        let script = PatchScript::parse(SCRIPT);
+
+        assert_eq!(script.hashes, vec![(Some(String::from("UnityPlayer.dll")), String::from("9d3a1ec2f9b2a5c44e9f7c4b1fe3a6d0"))]);
+        assert_eq!(script.servers, vec!["log-upload-os.example.com", "overseauspider.example.com"]);
+    }
+
+    #[test]
+    fn expected_hashes_of_files() {
+        let script = PatchScript::parse(r#"
+            echo "a7e4b6b2c8e2a4b8e5e2b4d6a6c8e0f2 is checked first"
+            [ "$(md5sum UnityPlayer.dll)" == "11111111111111111111111111111111" ]
+            sum=($(md5sum Data/xlua.dll))
+            if [ "${sum}" != "22222222222222222222222222222222" ]; then
+        "#);
+
+        assert_eq!(script.expected_hashes("UnityPlayer.dll"), vec!["11111111111111111111111111111111"]);
+        assert_eq!(script.expected_hashes("Data/xlua.dll"), vec!["22222222222222222222222222222222"]);
+        assert_eq!(script.expected_hashes("launcher.exe"), vec!["a7e4b6b2c8e2a4b8e5e2b4d6a6c8e0f2"]);
+    }
+
+    #[test]
+    fn game_file_paths() {
+        assert_eq!(PatchScript::game_file("UnityPlayer.dll").as_deref(), Some("UnityPlayer.dll"));
+        assert_eq!(PatchScript::game_file("./Data/xlua.dll").as_deref(), Some("Data/xlua.dll"));
+        assert_eq!(PatchScript::game_file("$GAME/Data/xlua.dll").as_deref(), Some("Data/xlua.dll"));
+        assert_eq!(PatchScript::game_file("${game_dir}/launcher.exe").as_deref(), Some("launcher.exe"));
+
+        assert_eq!(PatchScript::game_file("$DIR/UnityPlayer.dll"), None);
+        assert_eq!(PatchScript::game_file("/usr/bin/launcher.exe"), None);
+        assert_eq!(PatchScript::game_file("../UnityPlayer.dll"), None);
+        assert_eq!(PatchScript::game_file("$GAME_PATH"), None);
+    }
+
+    #[test]
+    fn blocked_in_hosts() {
+        let hosts = "127.0.0.1 localhost\n\
+            # 0.0.0.0 commented.example.com\n\
+            0.0.0.0 first.example.com second.example.com # telemetry\n\
+            192.168.1.2 lan.example.com\n";
+
+        let servers = ["second.example.com", "commented.example.com", "lan.example.com", "localhost"].map(String::from);
+
+        assert_eq!(blocked_in(hosts, &servers), vec![
+            (String::from("second.example.com"), true),
+            (String::from("commented.example.com"), false),
+            (String::from("lan.example.com"), false),
+            (String::from("localhost"), true)
+        ]);
+    }
+}
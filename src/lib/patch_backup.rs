@@ -0,0 +1,170 @@
+use std::path::{Path, PathBuf};
+use std::fs::File;
+use std::io::{Read, Write};
+
+use serde::{Serialize, Deserialize};
+
+use anime_game_core::version::Version;
+
+use crate::lib::hash;
+use crate::lib::patch::PatchScript;
+
+/// Name of the folder inside of the game folder where original files are stored
+pub const BACKUP_FOLDER: &str = ".patch-backup";
+
+/// Name of the file with the list of backed up files
+pub const MANIFEST_FILE: &str = "manifest.toml";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Patch version the files were backed up for
+    pub version: String,
+
+    /// Time of the backup creation
+    pub created: String,
+
+    pub files: Vec<BackupFile>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupFile {
+    /// Path relative to the game folder
+    pub path: PathBuf,
+
+    pub md5: String,
+    pub size: u64
+}
+
+impl Manifest {
+    pub fn read<T: AsRef<Path>>(backup: T) -> anyhow::Result<Self> {
+        let mut file = File::open(backup.as_ref().join(MANIFEST_FILE))?;
+        let mut toml = String::new();
+
+        file.read_to_string(&mut toml)?;
+
+        Ok(toml::from_str(&toml)?)
+    }
+
+    pub fn write<T: AsRef<Path>>(&self, backup: T) -> anyhow::Result<()> {
+        let mut file = File::create(backup.as_ref().join(MANIFEST_FILE))?;
+
+        file.write_all(toml::to_string(self)?.as_bytes())?;
+
+        Ok(())
+    }
+}
+
+/// Find backup made before applying the patch
+///
+/// Only one backup is stored at a time. Returns its folder and manifest
+pub fn find<T: AsRef<Path>>(game_path: T) -> anyhow::Result<Option<(PathBuf, Manifest)>> {
+    let folder = game_path.as_ref().join(BACKUP_FOLDER);
+
+    if !folder.exists() {
+        return Ok(None);
+    }
+
+    for entry in std::fs::read_dir(folder)? {
+        let path = entry?.path();
+
+        if path.join(MANIFEST_FILE).exists() {
+            let manifest = Manifest::read(&path)?;
+
+            return Ok(Some((path, manifest)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Copy game files the patch will modify to `.patch-backup/<version>` folder
+///
+/// Existing backup of the same version is kept as it was made from the original files.
+/// Backups of other versions are removed
+pub fn create<T: AsRef<Path>>(game_path: T, version: Version, script: &PatchScript) -> anyhow::Result<PathBuf> {
+    let game_path = game_path.as_ref();
+    let backup = game_path.join(BACKUP_FOLDER).join(version.to_string());
+
+    if backup.join(MANIFEST_FILE).exists() {
+        return Ok(backup);
+    }
+
+    if game_path.join(BACKUP_FOLDER).exists() {
+        std::fs::remove_dir_all(game_path.join(BACKUP_FOLDER))?;
+    }
+
+    std::fs::create_dir_all(&backup)?;
+
+    let mut manifest = Manifest {
+        version: version.to_string(),
+        created: chrono::Local::now().to_rfc3339(),
+        files: Vec::new()
+    };
+
+    for file in &script.files {
+        let mut path = game_path.join(file);
+
+        // Patch was already applied so its script has the original file
+        if game_path.join(format!("{file}.bak")).exists() {
+            path = game_path.join(format!("{file}.bak"));
+        }
+
+        if path.exists() {
+            let target = backup.join(file);
+
+            // Patched files may be located in subfolders
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            std::fs::copy(&path, target)?;
+
+            manifest.files.push(BackupFile {
+                path: PathBuf::from(file),
+                md5: hash::md5(&path)?,
+                size: std::fs::metadata(&path)?.len()
+            });
+        }
+    }
+
+    // Manifest is written last so incomplete backups are not used
+    manifest.write(&backup)?;
+
+    Ok(backup)
+}
+
+/// Restore original files from the backup and remove it
+///
+/// Copies of the original files made by the patch script (`.bak` files) are removed as well.
+/// Returns the list of restored files
+pub fn restore<T: AsRef<Path>, F: AsRef<Path>>(game_path: T, backup: F) -> anyhow::Result<Vec<PathBuf>> {
+    let game_path = game_path.as_ref();
+    let backup = backup.as_ref();
+
+    let manifest = Manifest::read(backup)?;
+
+    // Check the whole backup before touching any game file
+    for file in &manifest.files {
+        if hash::md5(backup.join(&file.path))? != file.md5 {
+            anyhow::bail!("Backup of {:?} is corrupted", file.path);
+        }
+    }
+
+    let mut restored = Vec::new();
+
+    for file in &manifest.files {
+        std::fs::copy(backup.join(&file.path), game_path.join(&file.path))?;
+
+        let bak = game_path.join(format!("{}.bak", file.path.to_string_lossy()));
+
+        if bak.exists() {
+            std::fs::remove_file(bak)?;
+        }
+
+        restored.push(file.path.clone());
+    }
+
+    std::fs::remove_dir_all(game_path.join(BACKUP_FOLDER))?;
+
+    Ok(restored)
+}
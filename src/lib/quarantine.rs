@@ -0,0 +1,171 @@
+use std::path::{Path, PathBuf};
+use std::fs::File;
+use std::io::{Read, Write};
+
+use serde::{Serialize, Deserialize};
+
+use anime_game_core::repairer::IntegrityFile;
+
+use crate::lib::move_file;
+
+/// Name of the file with the list of quarantined files
+pub const MANIFEST_FILE: &str = "manifest.toml";
+
+/// Name of the folder inside of the session where quarantined files are stored
+pub const FILES_FOLDER: &str = "files";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Path to the game the files were taken from
+    pub game_path: String,
+
+    /// Time of quarantining
+    pub created: String,
+
+    pub files: Vec<QuarantinedFile>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QuarantinedFile {
+    /// Path relative to the game folder
+    pub path: PathBuf,
+
+    /// Size of the broken file
+    pub size: u64,
+
+    /// Expected md5 hash from the integrity file
+    pub expected_md5: String,
+
+    /// Expected size from the integrity file
+    pub expected_size: u64
+}
+
+impl Manifest {
+    pub fn read<T: AsRef<Path>>(session: T) -> anyhow::Result<Self> {
+        let mut file = File::open(session.as_ref().join(MANIFEST_FILE))?;
+        let mut toml = String::new();
+
+        file.read_to_string(&mut toml)?;
+
+        Ok(toml::from_str(&toml)?)
+    }
+
+    pub fn write<T: AsRef<Path>>(&self, session: T) -> anyhow::Result<()> {
+        let mut file = File::create(session.as_ref().join(MANIFEST_FILE))?;
+
+        file.write_all(toml::to_string(self)?.as_bytes())?;
+
+        Ok(())
+    }
+}
+
+/// Move broken files into a new timestamped session inside of the quarantine folder
+/// 
+/// Files which don't exist in the game folder are skipped. Returns path to the session folder
+pub fn quarantine<T: AsRef<Path>, F: AsRef<Path>>(folder: T, game_path: F, files: &[IntegrityFile]) -> anyhow::Result<PathBuf> {
+    let game_path = game_path.as_ref();
+    let created = chrono::Local::now();
+
+    let name = created.format("%Y-%m-%d_%H-%M-%S").to_string();
+    let mut session = folder.as_ref().join(&name);
+
+    std::fs::create_dir_all(folder.as_ref())?;
+
+    // Another repair could quarantine files within the same second
+    let mut suffix = 1;
+
+    while let Err(err) = std::fs::create_dir(&session) {
+        if err.kind() != std::io::ErrorKind::AlreadyExists {
+            return Err(err.into());
+        }
+
+        suffix += 1;
+        session = folder.as_ref().join(format!("{}_{}", name, suffix));
+    }
+
+    let mut manifest = Manifest {
+        game_path: game_path.to_string_lossy().to_string(),
+        created: created.to_rfc3339(),
+        files: Vec::new()
+    };
+
+    for file in files {
+        let path = game_path.join(&file.path);
+
+        if let Ok(metadata) = std::fs::metadata(&path) {
+            if let Err(err) = move_file(&path, session.join(FILES_FOLDER).join(&file.path)) {
+                // Keep the list of already moved files so they can be restored
+                manifest.write(&session)?;
+
+                anyhow::bail!("Failed to move {:?}: {}", file.path, err);
+            }
+
+            manifest.files.push(QuarantinedFile {
+                path: file.path.clone(),
+                size: metadata.len(),
+                expected_md5: file.md5.clone(),
+                expected_size: file.size
+            });
+        }
+    }
+
+    manifest.write(&session)?;
+
+    Ok(session)
+}
+
+#[derive(Debug, Default)]
+pub struct Restored {
+    /// Files moved back to the game folder
+    pub restored: Vec<PathBuf>,
+
+    /// Files which are not in the session anymore, e.g. restored by the previous attempt
+    pub missing: Vec<PathBuf>,
+
+    /// Files which failed to be moved back with the reason
+    pub failed: Vec<(PathBuf, String)>
+}
+
+/// Move quarantined files back to the game folder
+/// 
+/// Manifest is updated after each restored file so failed restoring can be continued later.
+/// The session is removed only when all its files were restored
+pub fn restore<T: AsRef<Path>>(session: T) -> anyhow::Result<Restored> {
+    let session = session.as_ref();
+    let mut manifest = Manifest::read(session)?;
+
+    let game_path = PathBuf::from(&manifest.game_path);
+    let mut result = Restored::default();
+
+    let mut remaining = Vec::new();
+
+    for file in std::mem::take(&mut manifest.files) {
+        let quarantined = session.join(FILES_FOLDER).join(&file.path);
+
+        if !quarantined.exists() {
+            result.missing.push(file.path);
+
+            continue;
+        }
+
+        match move_file(&quarantined, game_path.join(&file.path)) {
+            Ok(()) => result.restored.push(file.path),
+            Err(err) => {
+                result.failed.push((file.path.clone(), err.to_string()));
+
+                remaining.push(file);
+            }
+        }
+    }
+
+    if remaining.is_empty() {
+        std::fs::remove_dir_all(session)?;
+    }
+
+    else {
+        manifest.files = remaining;
+        manifest.write(session)?;
+    }
+
+    Ok(result)
+}
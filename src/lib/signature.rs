@@ -0,0 +1,283 @@
+use std::path::Path;
+use std::process::Command;
+
+use crate::lib::list_files;
+
+/// Name of the file in the patch folder with sha256 checksums of all the patch files
+pub const CHECKSUMS_FILE: &str = "SHA256SUMS";
+
+/// Get fingerprints of the keys which made valid signatures from gpg status output
+///
+/// Both signing key and its primary key fingerprints are returned
+pub fn valid_signers(status: &str) -> Vec<String> {
+    let mut signers = Vec::new();
+
+    for line in status.lines() {
+        if let Some(info) = line.strip_prefix("[GNUPG:] VALIDSIG ") {
+            let fields = info.split_whitespace().collect::<Vec<&str>>();
+
+            // VALIDSIG <fingerprint> <date> <timestamp> <expire> <version> <reserved> <pubkey algo> <hash algo> <class> <primary key fingerprint>
+            for fingerprint in [fields.first(), fields.get(9)].into_iter().flatten() {
+                if !signers.iter().any(|signer| signer == fingerprint) {
+                    signers.push(fingerprint.to_string());
+                }
+            }
+        }
+    }
+
+    signers
+}
+
+/// Normalize full key fingerprint: remove spaces and `0x` prefix, and make it uppercase
+///
+/// Returns `None` if it's not a 40 characters hex fingerprint. Key ids are not accepted
+/// because keys with colliding ids can be generated
+pub fn parse_fingerprint(key: &str) -> Option<String> {
+    let key = key.replace(' ', "").to_uppercase();
+    let key = key.strip_prefix("0X").unwrap_or(&key);
+
+    if key.len() == 40 && key.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some(key.to_string())
+    } else {
+        None
+    }
+}
+
+/// Check if the fingerprint is in the trusted keys list
+pub fn is_trusted(fingerprint: &str, trusted_keys: &[String]) -> bool {
+    let fingerprint = fingerprint.to_uppercase();
+
+    trusted_keys.iter()
+        .filter_map(|key| parse_fingerprint(key))
+        .any(|key| key == fingerprint)
+}
+
+/// Run command and get its gpg status output
+fn status(command: &mut Command, stderr: bool) -> anyhow::Result<String> {
+    let output = command.output()?;
+
+    Ok(String::from_utf8_lossy(if stderr { &output.stderr } else { &output.stdout }).to_string())
+}
+
+/// Check that the checksums list has all the patch files and they match it
+fn check_checksums(patch_folder: &Path) -> anyhow::Result<()> {
+    let checksums = std::fs::read_to_string(patch_folder.join(CHECKSUMS_FILE))?;
+
+    let listed = checksums.lines()
+        .filter_map(|line| line.split_once(char::is_whitespace))
+        .map(|(_, file)| {
+            let file = file.trim_start();
+            let file = file.strip_prefix('*').unwrap_or(file);
+
+            file.strip_prefix("./").unwrap_or(file).to_string()
+        })
+        .collect::<Vec<String>>();
+
+    for file in list_files(patch_folder)? {
+        let name = file.to_string_lossy();
+
+        if file.starts_with(".git") || name == CHECKSUMS_FILE || name.starts_with(&format!("{CHECKSUMS_FILE}.")) {
+            continue;
+        }
+
+        if !listed.iter().any(|listed| *listed == name) {
+            anyhow::bail!("{:?} is not in {CHECKSUMS_FILE}", file);
+        }
+    }
+
+    let output = Command::new("sha256sum")
+        .args(["--check", "--strict", "--quiet", CHECKSUMS_FILE])
+        .current_dir(patch_folder)
+        .output()?;
+
+    if !output.status.success() {
+        anyhow::bail!("Patch files don't match {CHECKSUMS_FILE}: {}", String::from_utf8_lossy(&output.stdout).trim());
+    }
+
+    Ok(())
+}
+
+/// Verify signatures of the checked out patch revision
+///
+/// Signed commit and signed tags pointing to it are accepted only if the working tree
+/// has no changes or untracked files. Detached signature (`SHA256SUMS.sig` or `SHA256SUMS.asc`)
+/// of the checksums list is accepted only if the list covers all the patch files and they match it.
+/// Trusted keys should be full fingerprints. Returns description of the trusted signature
+pub fn verify<T: AsRef<Path>>(patch_folder: T, trusted_keys: &[String]) -> anyhow::Result<String> {
+    let patch_folder = patch_folder.as_ref();
+
+    // Short key ids can be forged, and the patch is run as root
+    for key in trusted_keys {
+        if parse_fingerprint(key).is_none() {
+            anyhow::bail!("patch.trusted_keys: {key:?} is not a full 40 characters key fingerprint");
+        }
+    }
+
+    let mut signatures = Vec::new();
+    let mut problems = Vec::new();
+
+    // Files not from the signed revision would be run by the patch as well
+    match status(Command::new("git").arg("-C").arg(patch_folder).args(["status", "--porcelain", "--ignored"]), false) {
+        Ok(changes) if changes.trim().is_empty() => {
+            // git prints gpg status to stderr
+            let commit = status(Command::new("git").arg("-C").arg(patch_folder).args(["verify-commit", "--raw", "HEAD"]), true)?;
+
+            signatures.push((String::from("commit"), valid_signers(&commit)));
+
+            let tags = status(Command::new("git").arg("-C").arg(patch_folder).args(["tag", "--points-at", "HEAD"]), false)?;
+
+            for tag in tags.lines().filter(|tag| !tag.is_empty()) {
+                let output = status(Command::new("git").arg("-C").arg(patch_folder).args(["verify-tag", "--raw", tag]), true)?;
+
+                signatures.push((format!("tag {tag}"), valid_signers(&output)));
+            }
+        },
+        Ok(changes) => problems.push(format!(
+            "Patch folder has changes not from the checked out revision, so its signature can't be used: {}",
+            changes.lines().take(5).map(|line| line.trim()).collect::<Vec<&str>>().join(", ")
+        )),
+
+        // Patch synced from an archive is not a git repository
+        Err(_) => ()
+    }
+
+    for extension in ["sig", "asc"] {
+        let signature = patch_folder.join(format!("{CHECKSUMS_FILE}.{extension}"));
+
+        if signature.exists() {
+            let output = status(Command::new("gpg").args(["--status-fd", "1", "--verify"]).arg(&signature).arg(patch_folder.join(CHECKSUMS_FILE)), false)?;
+            let signers = valid_signers(&output);
+
+            // Checksums are useful only if they're made by a trusted key
+            if signers.iter().any(|signer| is_trusted(signer, trusted_keys)) {
+                if let Err(err) = check_checksums(patch_folder) {
+                    problems.push(err.to_string());
+
+                    continue;
+                }
+            }
+
+            signatures.push((format!("{:?}", signature), signers));
+        }
+    }
+
+    let mut untrusted = Vec::new();
+
+    for (source, signers) in signatures {
+        for signer in signers {
+            if is_trusted(&signer, trusted_keys) {
+                return Ok(format!("{source} is signed by trusted key {signer}"));
+            }
+
+            untrusted.push(signer);
+        }
+    }
+
+    if !untrusted.is_empty() {
+        problems.push(format!("Patch revision is signed by untrusted keys: {}", untrusted.join(", ")));
+    }
+
+    if problems.is_empty() {
+        anyhow::bail!("Patch revision is not signed");
+    }
+
+    anyhow::bail!("{}", problems.join(". "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRIMARY_KEY: &str = "4F8FAFBD0A3B799E89ED31240996C420D4AA11E2";
+    const SUBKEY: &str = "6E4E13D8C04953DACF53F9A16813195AE9A7DCB9";
+
+    // gpg --status-fd 1 --verify output for a signature made by the signing subkey
+    const SUBKEY_STATUS: &str = "\
+[GNUPG:] NEWSIG
+[GNUPG:] KEY_CONSIDERED 4F8FAFBD0A3B799E89ED31240996C420D4AA11E2 0
+[GNUPG:] SIG_ID qo6Y3bCIPdAesZloTU5MnS7txdA 2026-10-19 1792392887
+[GNUPG:] KEY_CONSIDERED 4F8FAFBD0A3B799E89ED31240996C420D4AA11E2 0
+[GNUPG:] GOODSIG 6813195AE9A7DCB9 Patch Maintainer <maintainer@example.com>
+[GNUPG:] VALIDSIG 6E4E13D8C04953DACF53F9A16813195AE9A7DCB9 2026-10-19 1792392887 0 4 0 22 8 00 4F8FAFBD0A3B799E89ED31240996C420D4AA11E2
+[GNUPG:] KEY_CONSIDERED 4F8FAFBD0A3B799E89ED31240996C420D4AA11E2 0
+[GNUPG:] KEY_CONSIDERED 4F8FAFBD0A3B799E89ED31240996C420D4AA11E2 0
+[GNUPG:] TRUST_ULTIMATE 0 pgp
+";
+
+    // Signature made by the primary key
+    const PRIMARY_STATUS: &str = "\
+[GNUPG:] NEWSIG
+[GNUPG:] KEY_CONSIDERED 4F8FAFBD0A3B799E89ED31240996C420D4AA11E2 0
+[GNUPG:] SIG_ID 2dZCnRABF2YV1Yo4vNHUQKqsrHw 2026-10-19 1792392890
+[GNUPG:] KEY_CONSIDERED 4F8FAFBD0A3B799E89ED31240996C420D4AA11E2 0
+[GNUPG:] GOODSIG 0996C420D4AA11E2 Patch Maintainer <maintainer@example.com>
+[GNUPG:] VALIDSIG 4F8FAFBD0A3B799E89ED31240996C420D4AA11E2 2026-10-19 1792392890 0 4 0 22 8 00 4F8FAFBD0A3B799E89ED31240996C420D4AA11E2
+[GNUPG:] KEY_CONSIDERED 4F8FAFBD0A3B799E89ED31240996C420D4AA11E2 0
+[GNUPG:] TRUST_ULTIMATE 0 pgp
+";
+
+    // Signature made by a key missing in the keyring
+    const NO_PUBKEY_STATUS: &str = "\
+[GNUPG:] NEWSIG
+[GNUPG:] ERRSIG 6813195AE9A7DCB9 22 8 00 1792392887 9 6E4E13D8C04953DACF53F9A16813195AE9A7DCB9
+[GNUPG:] NO_PUBKEY 6813195AE9A7DCB9
+";
+
+    // Signed file was changed
+    const BAD_STATUS: &str = "\
+[GNUPG:] NEWSIG
+[GNUPG:] KEY_CONSIDERED 4F8FAFBD0A3B799E89ED31240996C420D4AA11E2 0
+[GNUPG:] KEY_CONSIDERED 4F8FAFBD0A3B799E89ED31240996C420D4AA11E2 0
+[GNUPG:] BADSIG 6813195AE9A7DCB9 Patch Maintainer <maintainer@example.com>
+";
+
+    #[test]
+    fn valid_signers_of_subkey_signature() {
+        assert_eq!(valid_signers(SUBKEY_STATUS), vec![SUBKEY, PRIMARY_KEY]);
+    }
+
+    #[test]
+    fn valid_signers_of_primary_key_signature() {
+        assert_eq!(valid_signers(PRIMARY_STATUS), vec![PRIMARY_KEY]);
+    }
+
+    #[test]
+    fn no_valid_signers_of_invalid_signatures() {
+        assert!(valid_signers(NO_PUBKEY_STATUS).is_empty());
+        assert!(valid_signers(BAD_STATUS).is_empty());
+    }
+
+    #[test]
+    fn parse_full_fingerprints_only() {
+        let fingerprint = Some(String::from("4F8FAFBD0A3B799E89ED31240996C420D4AA11E2"));
+
+        assert_eq!(parse_fingerprint("4F8FAFBD0A3B799E89ED31240996C420D4AA11E2"), fingerprint);
+        assert_eq!(parse_fingerprint("4f8f afbd 0a3b 799e 89ed  3124 0996 c420 d4aa 11e2"), fingerprint);
+        assert_eq!(parse_fingerprint("0x4F8FAFBD0A3B799E89ED31240996C420D4AA11E2"), fingerprint);
+
+        assert_eq!(parse_fingerprint("D4AA11E2"), None);
+        assert_eq!(parse_fingerprint("0x0996C420D4AA11E2"), None);
+        assert_eq!(parse_fingerprint("4F8FAFBD0A3B799E89ED31240996C420D4AA11EG"), None);
+        assert_eq!(parse_fingerprint("4F8FAFBD0A3B799E89ED31240996C420D4AA11E200"), None);
+    }
+
+    #[test]
+    fn trusted_by_full_fingerprint() {
+        let trusted_keys = [String::from("4f8f afbd 0a3b 799e 89ed  3124 0996 c420 d4aa 11e2"), String::from("D4AA11E2")];
+
+        assert!(is_trusted("4F8FAFBD0A3B799E89ED31240996C420D4AA11E2", &trusted_keys));
+        assert!(is_trusted("4f8fafbd0a3b799e89ed31240996c420d4aa11e2", &trusted_keys));
+
+        // Key ids and fingerprints ending with them are not trusted
+        assert!(!is_trusted("D4AA11E2", &trusted_keys));
+        assert!(!is_trusted("00000000000000000000000000000000 D4AA11E2", &trusted_keys));
+        assert!(!is_trusted("00000000000000000000000000000000D4AA11E2", &trusted_keys));
+    }
+
+    #[test]
+    fn verify_rejects_short_trusted_keys() {
+        let err = verify("/nonexistent", &[String::from("D4AA11E2")]).unwrap_err();
+
+        assert!(err.to_string().contains("is not a full 40 characters key fingerprint"));
+    }
+}
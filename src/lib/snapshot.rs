@@ -0,0 +1,249 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::fs::File;
+use std::io::{Read, Write};
+
+use serde::{Serialize, Deserialize};
+
+use crate::lib::orphans;
+use crate::lib::patch_backup;
+use crate::lib::lock::LOCK_FILE;
+use crate::lib::{list_files, move_file, copy_file};
+
+/// Name of the folder inside of the game folder where files of the previous version are stored
+pub const BACKUP_FOLDER: &str = ".update-backup";
+
+/// Name of the file with the backup info
+pub const MANIFEST_FILE: &str = "backup.toml";
+
+/// Name of the folder inside of the backup where files are stored
+pub const FILES_FOLDER: &str = "files";
+
+/// Name of the file with the installed game version
+pub const VERSION_FILE: &str = ".version";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Game version before updating
+    pub version: String,
+
+    /// Game version the game was updated to
+    pub updated_to: String,
+
+    /// Time of the backup creation
+    pub created: String,
+
+    /// Whether the update was finished successfully
+    pub completed: bool,
+
+    /// Files of the previous version relative to the game folder
+    pub files: Vec<PathBuf>,
+
+    /// Files copied to the backup because the update replaces or deletes them
+    pub saved: Vec<PathBuf>
+}
+
+impl Manifest {
+    /// Read backup info of the game
+    ///
+    /// Returns `None` if there's no backup
+    pub fn read<T: AsRef<Path>>(game_path: T) -> anyhow::Result<Option<Self>> {
+        let path = backup_folder(game_path).join(MANIFEST_FILE);
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let mut file = File::open(path)?;
+        let mut toml = String::new();
+
+        file.read_to_string(&mut toml)?;
+
+        Ok(Some(toml::from_str(&toml)?))
+    }
+
+    pub fn write<T: AsRef<Path>>(&self, game_path: T) -> anyhow::Result<()> {
+        let mut file = File::create(backup_folder(game_path).join(MANIFEST_FILE))?;
+
+        file.write_all(toml::to_string(self)?.as_bytes())?;
+
+        Ok(())
+    }
+}
+
+/// Get path to the backup folder of the game
+pub fn backup_folder<T: AsRef<Path>>(game_path: T) -> PathBuf {
+    game_path.as_ref().join(BACKUP_FOLDER)
+}
+
+/// List game files except the backup itself and the lock file
+fn game_files<T: AsRef<Path>>(game_path: T) -> std::io::Result<Vec<PathBuf>> {
+    Ok(list_files(game_path)?
+        .into_iter()
+        .filter(|file| !file.starts_with(BACKUP_FOLDER) && file != Path::new(LOCK_FILE))
+        .collect())
+}
+
+/// Get game files which will be replaced or patched by the update archive
+/// 
+/// `entries` are names of the archive entries. Files patched by `.hdiff` entries
+/// and the version file are included. Files removed by the update are moved
+/// to the backup later with `move_to_backup`
+pub fn affected_files<T: AsRef<Path>>(game_path: T, entries: &[String]) -> Vec<PathBuf> {
+    let game_path = game_path.as_ref();
+
+    let mut affected = vec![PathBuf::from(VERSION_FILE)];
+
+    for entry in entries {
+        // Folders
+        if entry.ends_with('/') {
+            continue;
+        }
+
+        affected.push(PathBuf::from(entry.strip_suffix(".hdiff").unwrap_or(entry)));
+    }
+
+    let mut known = HashSet::new();
+
+    affected.into_iter()
+        .filter(|file| game_path.join(file).is_file() && known.insert(file.clone()))
+        .collect()
+}
+
+/// Get total size of the files which will be saved to the backup
+pub fn size<T: AsRef<Path>>(game_path: T, files: &[PathBuf]) -> u64 {
+    files.iter()
+        .filter_map(|file| std::fs::metadata(game_path.as_ref().join(file)).ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Save the list of current game files and copies of the files affected by the update
+/// to the backup folder, replacing the previous backup
+pub fn create<T: AsRef<Path>>(game_path: T, version: String, updated_to: String, affected: &[PathBuf]) -> anyhow::Result<Manifest> {
+    let game_path = game_path.as_ref();
+    let folder = backup_folder(game_path);
+
+    if folder.exists() {
+        std::fs::remove_dir_all(&folder)?;
+    }
+
+    std::fs::create_dir_all(&folder)?;
+
+    for file in affected {
+        copy_file(game_path.join(file), folder.join(FILES_FOLDER).join(file))?;
+    }
+
+    let manifest = Manifest {
+        version,
+        updated_to,
+        created: chrono::Local::now().to_rfc3339(),
+        completed: false,
+        files: game_files(game_path)?,
+        saved: affected.to_vec()
+    };
+
+    manifest.write(game_path)?;
+
+    Ok(manifest)
+}
+
+/// Move files removed by the update to the backup instead of deleting them
+/// 
+/// Files which don't exist in the game folder are skipped
+pub fn move_to_backup<T: AsRef<Path>>(game_path: T, manifest: &mut Manifest, files: &[PathBuf]) -> anyhow::Result<()> {
+    let game_path = game_path.as_ref();
+    let folder = backup_folder(game_path).join(FILES_FOLDER);
+
+    for file in files {
+        let path = game_path.join(file);
+
+        if !path.is_file() {
+            continue;
+        }
+
+        // Already copied because the update replaced it as well
+        if manifest.saved.contains(file) {
+            std::fs::remove_file(path)?;
+        }
+
+        else {
+            move_file(path, folder.join(file))?;
+
+            manifest.saved.push(file.clone());
+        }
+    }
+
+    manifest.write(game_path)
+}
+
+/// Move the patch backup into the update backup
+/// 
+/// Patch backup has files of the previous version so it can't be used to revert the patch
+/// after updating. It's restored together with the previous version
+pub fn stash_patch_backup<T: AsRef<Path>>(game_path: T) -> anyhow::Result<()> {
+    let game_path = game_path.as_ref();
+    let patch_backup = game_path.join(patch_backup::BACKUP_FOLDER);
+
+    if patch_backup.exists() {
+        std::fs::rename(patch_backup, backup_folder(game_path).join(patch_backup::BACKUP_FOLDER))?;
+    }
+
+    Ok(())
+}
+
+/// Restore the previous game version from the backup and remove it
+///
+/// Saved files are moved back to the game folder. Files which didn't exist before are removed,
+/// except configs, logs, screenshots and other files from the orphans allowlist
+pub fn restore<T: AsRef<Path>>(game_path: T) -> anyhow::Result<Manifest> {
+    let game_path = game_path.as_ref();
+    let folder = backup_folder(game_path);
+
+    let manifest = match Manifest::read(game_path)? {
+        Some(manifest) => manifest,
+        None => anyhow::bail!("There's no update backup")
+    };
+
+    let known = manifest.files.iter().collect::<HashSet<_>>();
+
+    for file in game_files(game_path)? {
+        if !known.contains(&file) && !orphans::is_allowed(&file) {
+            std::fs::remove_file(game_path.join(&file))?;
+        }
+    }
+
+    for file in &manifest.saved {
+        let backup = folder.join(FILES_FOLDER).join(file);
+        let current = game_path.join(file);
+
+        // Restored by the previous attempt
+        if !backup.exists() {
+            continue;
+        }
+
+        if std::fs::symlink_metadata(&current).is_ok() {
+            std::fs::remove_file(&current)?;
+        }
+
+        if let Err(err) = move_file(&backup, &current) {
+            anyhow::bail!("Failed to restore {:?}: {}", file, err);
+        }
+    }
+
+    let patch_backup = folder.join(patch_backup::BACKUP_FOLDER);
+
+    if patch_backup.exists() {
+        let current = game_path.join(patch_backup::BACKUP_FOLDER);
+
+        if current.exists() {
+            std::fs::remove_dir_all(&current)?;
+        }
+
+        std::fs::rename(patch_backup, current)?;
+    }
+
+    std::fs::remove_dir_all(folder)?;
+
+    Ok(manifest)
+}
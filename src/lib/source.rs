@@ -0,0 +1,70 @@
+use std::path::Path;
+
+use anime_game_core::repairer::IntegrityFile;
+use anime_game_core::installer::downloader::Downloader;
+
+use crate::lib::hash;
+use crate::lib::move_file;
+
+/// Check if the source is an HTTP server rather than a local folder
+pub fn is_remote(source: &str) -> bool {
+    source.starts_with("http://") || source.starts_with("https://")
+}
+
+/// Try to repair the file by taking it from the reference installation
+/// 
+/// The source can be either a path to a local game folder or an URL to a server
+/// which serves game files with the same structure. Returns `Ok(false)` if the source
+/// doesn't have the file or its copy doesn't match the integrity file
+/// 
+/// File is downloaded to the staging folder first and moved to the game only after verification
+pub fn try_repair<T: AsRef<Path>, F: AsRef<Path>>(source: &str, file: &IntegrityFile, game_path: T, staging_folder: F) -> anyhow::Result<bool> {
+    let path = game_path.as_ref().join(&file.path);
+    let temp_path = staging_folder.as_ref().join(&file.path);
+
+    if let Some(parent) = temp_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    if temp_path.exists() {
+        std::fs::remove_file(&temp_path)?;
+    }
+
+    if is_remote(source) {
+        let url = format!("{}/{}", source.trim_end_matches('/'), file.path.to_string_lossy());
+
+        let downloaded = match Downloader::new(url) {
+            Ok(mut downloader) => downloader.download_to(temp_path.to_string_lossy(), |_, _| ()).is_ok(),
+            Err(_) => false
+        };
+
+        if !downloaded {
+            // Downloader could leave a partially downloaded file
+            std::fs::remove_file(&temp_path).ok();
+
+            return Ok(false);
+        }
+    }
+
+    else {
+        let source_path = Path::new(source).join(&file.path);
+
+        if !source_path.exists() {
+            return Ok(false);
+        }
+
+        std::fs::copy(source_path, &temp_path)?;
+    }
+
+    let size = std::fs::metadata(&temp_path)?.len();
+
+    if size != file.size || !hash::md5(&temp_path)?.eq_ignore_ascii_case(&file.md5) {
+        std::fs::remove_file(&temp_path)?;
+
+        return Ok(false);
+    }
+
+    move_file(temp_path, path)?;
+
+    Ok(true)
+}
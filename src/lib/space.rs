@@ -0,0 +1,117 @@
+use std::path::{Path, PathBuf};
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+
+use crate::lib::output::*;
+use crate::lib::format_size;
+
+/// Get the closest existing folder of the path
+fn existing_parent<T: AsRef<Path>>(path: T) -> PathBuf {
+    let mut path = path.as_ref().to_path_buf();
+
+    while !path.exists() {
+        match path.parent() {
+            Some(parent) => path = parent.to_path_buf(),
+            None => break
+        }
+    }
+
+    path
+}
+
+/// Get available space in bytes on the filesystem the path is located on
+pub fn available<T: AsRef<Path>>(path: T) -> std::io::Result<u64> {
+    let path = CString::new(existing_parent(path).as_os_str().as_bytes())?;
+
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[derive(Debug, Clone)]
+pub struct Shortfall {
+    pub path: PathBuf,
+    pub required: u64,
+    pub available: u64
+}
+
+/// Check if there's enough free space for all the requirements
+/// 
+/// Requirements are pairs of paths and sizes which will be written there.
+/// Sizes of paths located on the same filesystem are summed up
+pub fn check(requirements: &[(PathBuf, u64)]) -> std::io::Result<Vec<Shortfall>> {
+    // Filesystem id, path and required size
+    let mut filesystems: Vec<(u64, PathBuf, u64)> = Vec::new();
+
+    for (path, size) in requirements {
+        let dev = std::fs::metadata(existing_parent(path))?.dev();
+
+        match filesystems.iter_mut().find(|(fs, _, _)| *fs == dev) {
+            Some((_, _, required)) => *required += size,
+            None => filesystems.push((dev, path.clone(), *size))
+        }
+    }
+
+    let mut shortfalls = Vec::new();
+
+    for (_, path, required) in filesystems {
+        let available = available(&path)?;
+
+        if available < required {
+            shortfalls.push(Shortfall {
+                path,
+                required,
+                available
+            });
+        }
+    }
+
+    Ok(shortfalls)
+}
+
+/// Check free space before installing anything
+/// 
+/// Prints shortfalls and returns `false` if the operation should be aborted
+pub fn preflight(requirements: &[(PathBuf, u64)], ignore: bool) -> bool {
+    let shortfalls = match check(requirements) {
+        Ok(shortfalls) => shortfalls,
+        Err(err) => {
+            warn(format!("Failed to check free space: {}", err));
+
+            return true;
+        }
+    };
+
+    if shortfalls.is_empty() {
+        return true;
+    }
+
+    let mut output = vec![String::from("Not enough free space:")];
+
+    for shortfall in &shortfalls {
+        output.push(format!(
+            "- {:?}: {} GB required, {} GB available ({} GB more needed)",
+            shortfall.path,
+            format_size(shortfall.required),
+            format_size(shortfall.available),
+            format_size(shortfall.required - shortfall.available)
+        ));
+    }
+
+    if ignore {
+        warn(output);
+
+        true
+    } else {
+        output.push(String::from("Use --ignore-space-check to continue anyway"));
+
+        error(output);
+
+        false
+    }
+}
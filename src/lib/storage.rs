@@ -0,0 +1,83 @@
+use std::path::{Path, PathBuf};
+use std::os::unix::fs::MetadataExt;
+
+/// Get sysfs folder of the block device the path is located on
+fn block_device<T: AsRef<Path>>(path: T) -> Option<PathBuf> {
+    let dev = std::fs::metadata(path).ok()?.dev();
+
+    // Same as gnu_dev_major and gnu_dev_minor
+    let major = ((dev >> 32) & 0xffff_f000) | ((dev >> 8) & 0x0000_0fff);
+    let minor = ((dev >> 12) & 0xffff_ff00) | (dev & 0x0000_00ff);
+
+    let device = PathBuf::from(format!("/sys/dev/block/{major}:{minor}"));
+
+    if device.exists() {
+        Some(device)
+    } else {
+        None
+    }
+}
+
+/// Check if the path is located on a rotational drive (HDD)
+/// 
+/// Returns `None` if it's impossible to find out (e.g. network or virtual filesystems)
+pub fn is_rotational<T: AsRef<Path>>(path: T) -> Option<bool> {
+    let device = block_device(path)?;
+
+    // Partitions don't have queue folder, but their parent devices do
+    for queue in [device.join("queue/rotational"), device.join("../queue/rotational")] {
+        if let Ok(value) = std::fs::read_to_string(queue) {
+            return Some(value.trim() == "1");
+        }
+    }
+
+    None
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageType {
+    Rotational,
+
+    /// Non-rotational drive other than NVMe: SATA SSD, eMMC, virtual or USB drive
+    Ssd,
+    Nvme,
+    Unknown
+}
+
+/// Find out type of the drive the path is located on
+pub fn storage_type<T: AsRef<Path>>(path: T) -> StorageType {
+    let path = path.as_ref();
+
+    match is_rotational(path) {
+        Some(true) => StorageType::Rotational,
+        Some(false) => {
+            let nvme = block_device(path)
+                .and_then(|device| device.canonicalize().ok())
+                .map(|device| device.iter().any(|component| component.to_string_lossy().starts_with("nvme")))
+                .unwrap_or(false);
+
+            if nvme {
+                StorageType::Nvme
+            } else {
+                StorageType::Ssd
+            }
+        },
+        None => StorageType::Unknown
+    }
+}
+
+/// Suggest number of threads to verify files located on the path
+/// 
+/// Returns number of threads and the reason why it was chosen
+pub fn suggest_verify_threads<T: AsRef<Path>>(path: T) -> (usize, String) {
+    let cpus = std::thread::available_parallelism()
+        .map(|cpus| cpus.get())
+        .unwrap_or(1);
+
+    match storage_type(path) {
+        StorageType::Rotational => (2, String::from("game is located on a rotational drive, more threads will make it seek all the time")),
+        StorageType::Ssd => (cpus.min(4), format!("game is located on a non-rotational drive which is saturated by few threads, {cpus} CPUs available")),
+        StorageType::Nvme => (cpus, format!("game is located on an NVMe drive, using all {cpus} CPUs")),
+        StorageType::Unknown => (cpus.min(4), format!("failed to find out the drive type, {cpus} CPUs available"))
+    }
+}
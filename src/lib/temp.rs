@@ -0,0 +1,86 @@
+use std::path::{Path, PathBuf};
+
+use crate::lib::config;
+use crate::lib::folder_size;
+
+/// Name of the folder inside of the temp folder where staging data of all the processes is stored
+pub const STAGING_FOLDER: &str = "anime-game-cli";
+
+/// Get temp folder
+/// 
+/// Priority: `--temp-dir` argument, `paths.temp` config value, system temp folder
+pub fn temp_folder(custom: Option<&str>) -> PathBuf {
+    if let Some(custom) = custom {
+        return PathBuf::from(custom);
+    }
+
+    // Don't create default config file just to read the temp folder
+    if Path::new(config::CONFIG_FILE).exists() {
+        if let Ok(config) = config::get() {
+            if !config.paths.temp.is_empty() {
+                return PathBuf::from(config.paths.temp);
+            }
+        }
+    }
+
+    std::env::temp_dir()
+}
+
+/// Get (and create) staging folder of the current process
+/// 
+/// Every process has its own folder so it can be removed when the process is not running anymore
+pub fn staging_folder(custom: Option<&str>) -> std::io::Result<PathBuf> {
+    let folder = temp_folder(custom)
+        .join(STAGING_FOLDER)
+        .join(std::process::id().to_string());
+
+    std::fs::create_dir_all(&folder)?;
+
+    Ok(folder)
+}
+
+/// Remove staging folder of the current process
+pub fn remove_staging_folder(custom: Option<&str>) {
+    let folder = temp_folder(custom)
+        .join(STAGING_FOLDER)
+        .join(std::process::id().to_string());
+
+    std::fs::remove_dir_all(folder).ok();
+}
+
+/// Remove staging folders of the processes which are not running anymore
+/// 
+/// Returns removed folders and their sizes
+pub fn clean_stale<T: AsRef<Path>>(temp_folder: T) -> std::io::Result<Vec<(PathBuf, u64)>> {
+    let staging = temp_folder.as_ref().join(STAGING_FOLDER);
+
+    let mut removed = Vec::new();
+
+    if !staging.exists() {
+        return Ok(removed);
+    }
+
+    for entry in std::fs::read_dir(staging)? {
+        let path = entry?.path();
+
+        let running = path.file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.parse::<u32>().ok())
+            .map(|pid| Path::new(&format!("/proc/{pid}")).exists())
+            .unwrap_or(false);
+
+        if !running {
+            let size = folder_size(&path).unwrap_or(0);
+
+            if path.is_dir() {
+                std::fs::remove_dir_all(&path)?;
+            } else {
+                std::fs::remove_file(&path)?;
+            }
+
+            removed.push((path, size));
+        }
+    }
+
+    Ok(removed)
+}
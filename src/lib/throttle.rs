@@ -0,0 +1,54 @@
+use std::sync::{Mutex, Condvar};
+
+/// Counting semaphore used to limit amount of resources shared between threads
+#[derive(Debug)]
+pub struct Semaphore {
+    total: u64,
+    available: Mutex<u64>,
+    condvar: Condvar
+}
+
+impl Semaphore {
+    pub fn new(total: u64) -> Self {
+        Self {
+            total,
+            available: Mutex::new(total),
+            condvar: Condvar::new()
+        }
+    }
+
+    /// Block current thread until requested amount is available
+    /// 
+    /// Amount bigger than the semaphore's total is clamped so it can't block forever
+    pub fn acquire(&self, amount: u64) -> SemaphoreGuard<'_> {
+        let amount = amount.min(self.total);
+
+        let mut available = self.available.lock().unwrap();
+
+        while *available < amount {
+            available = self.condvar.wait(available).unwrap();
+        }
+
+        *available -= amount;
+
+        SemaphoreGuard {
+            semaphore: self,
+            amount
+        }
+    }
+}
+
+/// Releases acquired amount when dropped
+#[derive(Debug)]
+pub struct SemaphoreGuard<'a> {
+    semaphore: &'a Semaphore,
+    amount: u64
+}
+
+impl Drop for SemaphoreGuard<'_> {
+    fn drop(&mut self) {
+        *self.semaphore.available.lock().unwrap() += self.amount;
+
+        self.semaphore.condvar.notify_all();
+    }
+}
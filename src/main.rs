@@ -9,12 +9,18 @@ fn main() {
         commands::Voice::new(),
         commands::Patch::new(),
         commands::Game::new(),
+        commands::Repair::new(),
+        commands::Cache::new(),
         commands::Run::new(),
         commands::Help::new()
     ]);
 
     println!("\n Anime Game CLI\n");
 
+    // Remove data left by interrupted downloads
+    // Folders from --temp-dir aren't known here so only the configured one is cleaned
+    lib::temp::clean_stale(lib::temp::temp_folder(None)).ok();
+
     match manager.execute(std::env::args().skip(1).collect()) {
         Ok(_) => (),
         